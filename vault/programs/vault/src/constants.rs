@@ -1,4 +1,16 @@
 pub const VAULT_AUTHORITY_SEED: &str = "vault-authority";
 pub const REDEEMABLE_MINT_SEED: &str = "redeemable-mint";
 pub const VAULT_USDC_SEED: &str = "vault-usdc";
-pub const USER_REDEEMABLE_SEED: &str = "user-redeemable";
\ No newline at end of file
+pub const USER_REDEEMABLE_SEED: &str = "user-redeemable";
+
+pub const UNIX_WEEK: u64 = 604_800;
+
+pub const RISK_LIMIT_PRECISION_DENOMINATOR: u64 = 100_000_000; // 100%
+
+// Pyth checks applied to the spot price used for sizing and margin.
+pub const ORACLE_MAX_SLOT_AGE: u64 = 25; // ~10 seconds
+pub const ORACLE_MAX_CONFIDENCE_PCT: u64 = 2_000_000; // 2%, NATIVE_PRECISION_DENOMINATOR
+
+// Max distance of a Zeta mark price from our Black-Scholes theo before we
+// refuse to sell against it.
+pub const MAX_MARK_PRICE_DEVIATION_PCT: u64 = 10_000_000; // 10%, NATIVE_PRECISION_DENOMINATOR
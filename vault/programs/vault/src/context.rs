@@ -1,6 +1,7 @@
 use crate::constants::*;
-//use crate::zeta_context::*;
 use crate::*;
+use zeta_sdk::zeta_context::nested::*;
+use zeta_sdk::zeta_context::{PlaceOrder, SettlePositions};
 
 #[derive(Accounts)]
 #[instruction(vault_name: String, vault_lamports: u64, bumps: VaultBumps)]
@@ -263,6 +264,51 @@ pub struct RolloverVault<'info> {
         constraint = vault.vault_admin == vault_admin.key() @ ErrorCode::InvalidVaultAdmin
     )]
     pub vault: Box<Account<'info, Vault>>,
+    // Zeta Accounts
+    pub zeta_group: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRiskLimits<'info> {
+    pub vault_admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = vault.vault_admin == vault_admin.key() @ ErrorCode::InvalidVaultAdmin
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateMarket<'info> {
+    pub vault: Box<Account<'info, Vault>>,
+    pub zeta_group: AccountInfo<'info>,
+    pub greeks: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SellPut<'info> {
+    pub vault_admin: Signer<'info>,
+    #[account(
+        constraint = vault.vault_admin == vault_admin.key() @ ErrorCode::InvalidVaultAdmin
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(
+        seeds = [VAULT_USDC_SEED.as_bytes(), vault.vault_name.as_ref().strip()],
+        bump = vault.bumps.vault_usdc
+    )]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+    pub zeta_program: AccountInfo<'info>,
+    pub place_order_cpi_accounts: PlaceOrder<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleVaultPositions<'info> {
+    pub vault: Box<Account<'info, Vault>>,
+    pub zeta_program: AccountInfo<'info>,
+    // The vault's Zeta margin account.
+    #[account(mut)]
+    pub margin_account: AccountInfo<'info>,
+    pub settle_positions_cpi_accounts: SettlePositions<'info>,
 }
 
 #[account]
@@ -277,6 +323,7 @@ pub struct Vault {
     pub vault_usdc: Pubkey,
 
     pub epoch_times: EpochTimes,
+    pub risk_limits: RiskLimits,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
@@ -290,6 +337,43 @@ pub struct EpochTimes {
     pub epoch_cadence: u64,    // spacing between successive epochs in seconds
 }
 
+// Offsets in seconds used to derive `EpochTimes` from a Zeta expiry series.
+// Settlement starts at the series' expiry_ts, the auction is timed from its active_ts.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct EpochSchedule {
+    pub deposit_duration: u64,    // start_epoch -> end_deposits
+    pub deposit_cutoff: u64,      // end_deposits -> start_auction
+    pub auction_offset: u64,      // active_ts -> start_auction
+    pub auction_duration: u64,    // start_auction -> end_auction
+    pub settlement_duration: u64, // start_settlement -> end_epoch
+    pub epoch_cadence: u64,
+}
+
+// How `initialize_vault` sets the first epoch.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum EpochConfig {
+    // Hand-picked epoch times.
+    Manual { epoch_times: EpochTimes },
+    // Derived from a Zeta expiry series, the zeta group is passed as the
+    // first remaining account.
+    ZetaExpiry {
+        expiry_index: u8,
+        schedule: EpochSchedule,
+    },
+}
+
+// Limits enforced before every option order the vault places.
+// Percentages use RISK_LIMIT_PRECISION_DENOMINATOR (100%), sizes are in
+// Zeta lots (POSITION_PRECISION), ages in seconds. Zeroed limits reject
+// every order.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct RiskLimits {
+    pub max_notional_pct: u64,         // max strike notional written, as a fraction of NAV
+    pub max_contracts_per_market: u64, // max open short contracts in a single market
+    pub min_margin_buffer_pct: u64,    // min margin buffer left after the order, as a fraction of NAV
+    pub max_greeks_age: u64,           // max age of Zeta greeks used for selection, sizing and NAV
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Clone)]
 pub struct VaultBumps {
     pub vault: u8,
@@ -10,11 +10,13 @@ use jet_proto_v1_cpi::accounts::*;
 use jet_proto_v1_cpi::{Amount, DepositCollateralBumpSeeds, WithdrawCollateralBumpSeeds};
 use std::cmp::min;
 use crate::context::*;
+// Shadows the epoch schedule sysvar of the anchor prelude.
+use crate::context::EpochSchedule;
 
 pub mod address;
 pub mod constants;
 pub mod context;
-pub mod pyth_client;
+pub mod utils;
 use constants::*;
 
 declare_id!("8KFe29BGwPevewGY147ytq2mSGuNVRtM4JaikvF6D26G");
@@ -30,14 +32,25 @@ pub mod vault {
 
     use super::*;
 
-    #[access_control(validate_epoch_times(epoch_times))]
     pub fn initialize_vault(
         ctx: Context<InitializeVault>,
         vault_name: String,
         vault_lamports: u64,
         bumps: VaultBumps,
-        epoch_times: EpochTimes,
+        epoch_config: EpochConfig,
     ) -> ProgramResult {
+        let current_ts = Clock::get()?.unix_timestamp;
+        let epoch_times = match epoch_config {
+            EpochConfig::Manual { epoch_times } => epoch_times,
+            EpochConfig::ZetaExpiry { expiry_index, schedule } => {
+                let zeta_group = ctx
+                    .remaining_accounts
+                    .first()
+                    .ok_or(ErrorCode::InvalidExpirySeries)?;
+                get_zeta_epoch_times(zeta_group, expiry_index, &schedule, current_ts)?
+            }
+        };
+        check_epoch_times(epoch_times, current_ts)?;
 
         let vault = &mut ctx.accounts.vault;
 
@@ -399,6 +412,53 @@ pub mod vault {
 
         Ok(())
     }
+
+    #[access_control(validate_risk_limits(&risk_limits))]
+    pub fn set_risk_limits(ctx: Context<SetRiskLimits>, risk_limits: RiskLimits) -> ProgramResult {
+        msg!("Set vault risk limits");
+        ctx.accounts.vault.risk_limits = risk_limits;
+        Ok(())
+    }
+
+    // Starts the next epoch on a Zeta expiry series instead of hand-picked times.
+    #[access_control(epoch_over(&ctx.accounts.vault) zeta_not_halted(&ctx.accounts.zeta_group))]
+    pub fn rollover_vault(
+        ctx: Context<RolloverVault>,
+        expiry_index: u8,
+        schedule: EpochSchedule,
+    ) -> ProgramResult {
+        msg!("Rollover vault");
+        let current_ts = Clock::get()?.unix_timestamp;
+        let epoch_times =
+            get_zeta_epoch_times(&ctx.accounts.zeta_group, expiry_index, &schedule, current_ts)?;
+        check_epoch_times(epoch_times, current_ts)?;
+        ctx.accounts.vault.epoch_times = epoch_times;
+        Ok(())
+    }
+
+    #[access_control(auction_phase(&ctx.accounts.vault) zeta_not_halted(&ctx.accounts.zeta_group))]
+    pub fn validate_market(ctx: Context<ValidateMarket>, delta: u8) -> ProgramResult {
+        utils::validate_market(ctx, delta)
+    }
+
+    #[access_control(
+        auction_phase(&ctx.accounts.vault)
+        zeta_not_halted(&ctx.accounts.place_order_cpi_accounts.zeta_group)
+    )]
+    pub fn sell_put(ctx: Context<SellPut>, price: u64) -> ProgramResult {
+        utils::sell_put(ctx, price)
+    }
+
+    #[access_control(
+        settlement_phase(&ctx.accounts.vault)
+        zeta_not_halted(&ctx.accounts.settle_positions_cpi_accounts.zeta_group)
+    )]
+    pub fn settle_vault_positions(
+        ctx: Context<SettleVaultPositions>,
+        expiry_index: u8,
+    ) -> ProgramResult {
+        utils::settle_vault_positions(ctx, expiry_index)
+    }
 }
 
 #[macro_export]
@@ -464,14 +524,29 @@ pub enum ErrorCode {
     InvalidUserUsdcAccountOwner,
     #[msg("Invalid vault admin")]
     InvalidVaultAdmin,
+    #[msg("Risk limit percentages must not exceed 100%")]
+    InvalidRiskLimits,
+    #[msg("Order would exceed the vault's max notional")]
+    MaxNotionalExceeded,
+    #[msg("Order would exceed the vault's max contracts per market")]
+    MaxContractsExceeded,
+    #[msg("Order would breach the vault's min margin buffer")]
+    InsufficientMarginBuffer,
+    #[msg("Zeta group is halted")]
+    ZetaGroupHalted,
+    #[msg("Expiry series is not initialized or live")]
+    InvalidExpirySeries,
+    #[msg("Delta must be between 0 and 100")]
+    InvalidDelta,
+    #[msg("Math overflow")]
+    MathOverflow,
 }
 
 // Access control modifiers.
 
-// Asserts the vault starts in the future.
-fn validate_epoch_times(epoch_times: EpochTimes) -> ProgramResult {
-    let clock = Clock::get()?;
-    if epoch_times.start_epoch <= clock.unix_timestamp {
+// Asserts the vault starts after `current_ts` and its epoch times are sequential.
+fn check_epoch_times(epoch_times: EpochTimes, current_ts: i64) -> ProgramResult {
+    if epoch_times.start_epoch <= current_ts {
         return Err(ErrorCode::VaultFuture.into());
     }
     msg!("{}", epoch_times.start_epoch < epoch_times.end_deposits);
@@ -491,6 +566,74 @@ fn validate_epoch_times(epoch_times: EpochTimes) -> ProgramResult {
     Ok(())
 }
 
+// Derives the epoch times from the expiry series at `expiry_index` of `zeta_group`.
+fn get_zeta_epoch_times(
+    zeta_group: &AccountInfo,
+    expiry_index: u8,
+    schedule: &EpochSchedule,
+    current_ts: i64,
+) -> Result<EpochTimes> {
+    if expiry_index as usize >= zeta_sdk::zeta_constants::ACTIVE_EXPIRIES {
+        return Err(ErrorCode::InvalidExpirySeries.into());
+    }
+    let expiry_series = zeta_sdk::zeta_utils::load_zeta_group(zeta_group)
+        .map_err(ProgramError::from)?
+        .expiry_series[expiry_index as usize];
+    derive_epoch_times(&expiry_series, schedule, current_ts as u64)
+}
+
+// Derives the epoch schedule around a Zeta expiry series. No offset is added
+// here: Zeta already applies EPOCH_OFFSET to `expiry_ts`, so settlement lines
+// up with Deribit as is.
+fn derive_epoch_times(
+    expiry_series: &zeta_sdk::zeta_account::ExpirySeries,
+    schedule: &EpochSchedule,
+    current_ts: u64,
+) -> Result<EpochTimes> {
+    match expiry_series.get_status(current_ts) {
+        zeta_sdk::zeta_account::ExpirySeriesStatus::Initialized
+        | zeta_sdk::zeta_account::ExpirySeriesStatus::Live => {}
+        _ => return Err(ErrorCode::InvalidExpirySeries.into()),
+    }
+
+    let start_auction = expiry_series
+        .active_ts
+        .checked_add(schedule.auction_offset)
+        .ok_or(ErrorCode::SeqTimes)?;
+    let end_auction = start_auction
+        .checked_add(schedule.auction_duration)
+        .ok_or(ErrorCode::SeqTimes)?;
+    let end_deposits = start_auction
+        .checked_sub(schedule.deposit_cutoff)
+        .ok_or(ErrorCode::SeqTimes)?;
+    let start_epoch = end_deposits
+        .checked_sub(schedule.deposit_duration)
+        .ok_or(ErrorCode::SeqTimes)?;
+    let end_epoch = expiry_series
+        .expiry_ts
+        .checked_add(schedule.settlement_duration)
+        .ok_or(ErrorCode::SeqTimes)?;
+
+    Ok(EpochTimes {
+        start_epoch: start_epoch as i64,
+        end_deposits: end_deposits as i64,
+        start_auction: start_auction as i64,
+        end_auction: end_auction as i64,
+        start_settlement: expiry_series.expiry_ts as i64,
+        end_epoch: end_epoch as i64,
+        epoch_cadence: schedule.epoch_cadence,
+    })
+}
+
+// Asserts the current epoch has finished.
+fn epoch_over(vault: &Vault) -> ProgramResult {
+    let clock = Clock::get()?;
+    if clock.unix_timestamp <= vault.epoch_times.end_epoch {
+        return Err(ErrorCode::EpochNotOver.into());
+    }
+    Ok(())
+}
+
 // Asserts the vault is still accepting deposits and withdrawals.
 fn deposit_withdraw_phase(vault: &Vault) -> ProgramResult {
     let clock = Clock::get()?;
@@ -504,6 +647,46 @@ fn deposit_withdraw_phase(vault: &Vault) -> ProgramResult {
     Ok(())
 }
 
+// Asserts the risk limit percentages are within [0, 100%].
+fn validate_risk_limits(risk_limits: &RiskLimits) -> ProgramResult {
+    if risk_limits.max_notional_pct > RISK_LIMIT_PRECISION_DENOMINATOR
+        || risk_limits.min_margin_buffer_pct > RISK_LIMIT_PRECISION_DENOMINATOR
+    {
+        return Err(ErrorCode::InvalidRiskLimits.into());
+    }
+    Ok(())
+}
+
+// Asserts the vault is in its auction phase.
+fn auction_phase(vault: &Vault) -> ProgramResult {
+    let clock = Clock::get()?;
+    if clock.unix_timestamp <= vault.epoch_times.start_auction {
+        return Err(ErrorCode::StartAuctionTime.into());
+    } else if clock.unix_timestamp > vault.epoch_times.end_auction {
+        return Err(ErrorCode::EndAuctionTime.into());
+    }
+    Ok(())
+}
+
+// Asserts the vault is in its settlement phase.
+fn settlement_phase(vault: &Vault) -> ProgramResult {
+    let clock = Clock::get()?;
+    if clock.unix_timestamp <= vault.epoch_times.start_settlement {
+        return Err(ErrorCode::StartSettlementTime.into());
+    } else if clock.unix_timestamp > vault.epoch_times.end_epoch {
+        return Err(ErrorCode::EndEpochTime.into());
+    }
+    Ok(())
+}
+
+// Asserts the Zeta group the vault trades on is not halted.
+fn zeta_not_halted(zeta_group: &AccountInfo) -> ProgramResult {
+    if zeta_sdk::zeta_utils::load_zeta_group(zeta_group)?.is_halted() {
+        return Err(ErrorCode::ZetaGroupHalted.into());
+    }
+    Ok(())
+}
+
 /// Trait to allow trimming ascii whitespace from a &[u8].
 pub trait StripAsciiWhitespace {
     /// Trim ascii whitespace (based on `is_ascii_whitespace()`) from the
@@ -521,3 +704,122 @@ impl<T: Deref<Target = [u8]>> StripAsciiWhitespace for T {
         &self[from..=to]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+    use zeta_sdk::zeta_account::{ExpirySeries, ZetaGroup};
+
+    const NOW: u64 = 1_640_000_000;
+    const HOUR: u64 = 3_600;
+    const DAY: u64 = 86_400;
+
+    // Weekly series listed a day from now.
+    fn expiry_series() -> ExpirySeries {
+        ExpirySeries {
+            active_ts: NOW + DAY,
+            expiry_ts: NOW + 8 * DAY,
+            dirty: false,
+            padding: [0; 15],
+        }
+    }
+
+    fn schedule() -> EpochSchedule {
+        EpochSchedule {
+            deposit_duration: 12 * HOUR,
+            deposit_cutoff: HOUR,
+            auction_offset: 2 * HOUR,
+            auction_duration: HOUR,
+            settlement_duration: DAY,
+            // Deposits open before the series is active, settlement ends after it expires.
+            epoch_cadence: 9 * DAY,
+        }
+    }
+
+    #[test]
+    fn derived_epoch_times_are_valid() {
+        let epoch_times = derive_epoch_times(&expiry_series(), &schedule(), NOW).unwrap();
+        assert!(check_epoch_times(epoch_times, NOW as i64).is_ok());
+    }
+
+    #[test]
+    fn derives_auction_window_from_active_ts() {
+        let series = expiry_series();
+        let epoch_times = derive_epoch_times(&series, &schedule(), NOW).unwrap();
+        assert_eq!(
+            epoch_times.start_auction,
+            (series.active_ts + 2 * HOUR) as i64
+        );
+        assert_eq!(
+            epoch_times.end_auction,
+            (series.active_ts + 3 * HOUR) as i64
+        );
+        assert_eq!(epoch_times.end_deposits, (series.active_ts + HOUR) as i64);
+        assert_eq!(epoch_times.start_settlement, series.expiry_ts as i64);
+        assert_eq!(epoch_times.end_epoch, (series.expiry_ts + DAY) as i64);
+    }
+
+    #[test]
+    fn rejects_expired_or_uninitialized_series() {
+        let series = expiry_series();
+        assert!(derive_epoch_times(&series, &schedule(), series.expiry_ts).is_err());
+
+        let uninitialized = ExpirySeries {
+            active_ts: 0,
+            expiry_ts: 0,
+            dirty: false,
+            padding: [0; 15],
+        };
+        assert!(derive_epoch_times(&uninitialized, &schedule(), NOW).is_err());
+    }
+
+    // Zeta owned ZetaGroup account, `halted` can only be set by Zeta so it is
+    // written into the raw account data.
+    fn zeta_group_account(zeta_group: &ZetaGroup, halted: bool) -> AccountInfo<'static> {
+        // `halted` is the first field of `HaltState`.
+        let halted_offset = std::ptr::addr_of!(zeta_group.halt_state) as usize
+            - zeta_group as *const ZetaGroup as usize;
+        let mut data = ZetaGroup::discriminator().to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(zeta_group));
+        data[8 + halted_offset] = halted as u8;
+        AccountInfo::new(
+            Box::leak(Box::new(Pubkey::new_unique())),
+            false,
+            false,
+            Box::leak(Box::new(0)),
+            Box::leak(data.into_boxed_slice()),
+            &zeta_sdk::ID,
+            false,
+            0,
+        )
+    }
+
+    #[test]
+    fn rejects_halted_zeta_group() {
+        let zeta_group: ZetaGroup = bytemuck::Zeroable::zeroed();
+        assert!(zeta_not_halted(&zeta_group_account(&zeta_group, false)).is_ok());
+        assert_eq!(
+            zeta_not_halted(&zeta_group_account(&zeta_group, true)).unwrap_err(),
+            ErrorCode::ZetaGroupHalted.into()
+        );
+    }
+
+    #[test]
+    fn derives_epoch_times_from_zeta_group() {
+        let mut zeta_group: ZetaGroup = bytemuck::Zeroable::zeroed();
+        zeta_group.expiry_series[1] = expiry_series();
+        let account = zeta_group_account(&zeta_group, false);
+
+        let epoch_times = get_zeta_epoch_times(&account, 1, &schedule(), NOW as i64).unwrap();
+        assert_eq!(
+            epoch_times.end_epoch,
+            derive_epoch_times(&expiry_series(), &schedule(), NOW)
+                .unwrap()
+                .end_epoch
+        );
+        // Slot 0 is uninitialized, slot 2 is out of range.
+        assert!(get_zeta_epoch_times(&account, 0, &schedule(), NOW as i64).is_err());
+        assert!(get_zeta_epoch_times(&account, 2, &schedule(), NOW as i64).is_err());
+    }
+}
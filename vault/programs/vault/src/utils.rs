@@ -1,65 +1,474 @@
-// Currently this code isn't used and is more here for reference on how
-// to conduct an auction fully on-chain
+// Auction and settlement logic for the vault's instructions, the phase and
+// halt checks are applied on the instructions in lib.rs.
+use crate::*;
+use std::cmp::min;
+use std::convert::TryInto;
+use zeta_sdk::pyth_client::PriceValidationConfig;
+use zeta_sdk::zeta_account::*;
+use zeta_sdk::zeta_constants::*;
+use zeta_sdk::zeta_utils::*;
+use zeta_sdk::{zeta_client, zeta_pricing, zeta_selection};
 
-#[access_control(auction_phase(&ctx.accounts.vault))]
 pub fn validate_market(ctx: Context<ValidateMarket>, delta: u8) -> ProgramResult {
-    assert!(delta >= 0 && delta <= 100);
+    if delta > 100 {
+        return Err(ErrorCode::InvalidDelta.into());
+    }
     // Delta values are stored at high precision for pricing
     let native_delta = (delta as u64)
         .checked_mul(10u64.pow(PRICING_PRECISION))
-        .unwrap();
-
-    // 1. Instrument selection: select the closest to 1w expiry and specific delta strike
-    let zeta_group =
-        deserialize_account_info_zerocopy::<ZetaGroup>(&ctx.accounts.zeta_group).unwrap();
-    // Get the expiry closest to 1 week
-    let closest_expiry_index = 0;
-    let closest_expiry_diff = unsigned_abs_diff(
-        UNIX_WEEK,
-        zeta_group.expiry_series[closest_expiry_index].expiry_ts,
-    )
-    .unwrap();
-    for (i, t) in zeta_group.expiry_series.iter().enumerate() {
-        let expiry_diff = unsigned_abs_diff(UNIX_WEEK, t.expiry_ts).unwrap();
-        if expiry_diff < closest_expiry_diff {
-            closest_expiry_index = i;
-        }
-    }
-    // Get strike closest to specified delta
-    let greeks = deserialize_account_info_zerocopy::<Greeks>(&ctx.accounts.greeks).unwrap();
-    let product_greeks = greeks.get_product_greeks_slice(closest_expiry_index);
-    let closest_delta_index = 0;
-    let closest_delta_diff =
-        unsigned_abs_diff(native_delta, product_greeks[closest_delta_index].delta).unwrap();
-    for (i, g) in product_greeks.iter().enumerate() {
-        let delta_diff = unsigned_abs_diff(native_delta, g.delta).unwrap();
-        if delta_diff < closest_delta_diff {
-            closest_delta_index = i;
-        }
-    }
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(100)
+        .ok_or(ErrorCode::MathOverflow)?;
 
-    // Sell puts on Zeta for given market
-    let market_index = closest_expiry_index * NUM_PRODUCTS_PER_SERIES + NUM_STRIKES + closest_delta_index;
+    // 1. Instrument selection: select the put closest to 1w expiry and specific delta strike
+    let zeta_group = load_zeta_group(&ctx.accounts.zeta_group)?;
+    let greeks = load_greeks(&ctx.accounts.greeks)?;
+    let clock = Clock::get()?;
+    greeks.check_live_freshness(
+        &zeta_group,
+        clock.unix_timestamp as u64,
+        ctx.accounts.vault.risk_limits.max_greeks_age,
+    )?;
+    let market_index = zeta_selection::select_market_index(
+        &zeta_group,
+        &greeks,
+        clock.unix_timestamp as u64,
+        UNIX_WEEK,
+        native_delta,
+        Kind::Put,
+    )?;
+    msg!("Selected market index {}", market_index);
 
     Ok(())
 }
 
-#[access_control(auction_phase(&ctx.accounts.vault))]
-pub fn sell_put(ctx: Context<SellPut>) -> ProgramResult {
+pub fn sell_put(ctx: Context<SellPut>, price: u64) -> ProgramResult {
     msg!("AUCTION: SELL PUT");
 
-    // Sell puts on Zeta for given market
-    let market_key = ctx.accounts.marketAccounts.market.key();
-    let market = ctx.accounts.marketAccounts.zeta_group.get_product_index_by_key(market_key);
-    let strike = market.strike;
-    let size = ctx.accounts.vault_usdc.amount.checked_div(strike).unwrap();
+    let size = {
+        let cpi_accounts = &ctx.accounts.place_order_cpi_accounts;
+        let zeta_group = load_zeta_group(&cpi_accounts.zeta_group)?;
+        let greeks = load_greeks(&cpi_accounts.greeks)?;
+        let margin_account = load_margin_account(&cpi_accounts.margin_account)?;
+        let spot = get_validated_native_oracle_price(
+            &cpi_accounts.oracle,
+            &PriceValidationConfig {
+                max_slot_age: ORACLE_MAX_SLOT_AGE,
+                max_confidence_pct: ORACLE_MAX_CONFIDENCE_PCT,
+            },
+        )?;
+        // Sizing, NAV and the margin checks below all read mark prices.
+        let now = Clock::get()?.unix_timestamp as u64;
+        greeks.check_live_freshness(
+            &zeta_group,
+            now,
+            ctx.accounts.vault.risk_limits.max_greeks_age,
+        )?;
+
+        // Sell puts on Zeta for given market
+        let market_index =
+            zeta_group.get_product_index_by_key(cpi_accounts.market_accounts.market.key)?;
+        // Don't sell against a mark price that has drifted from theo.
+        let theo = zeta_pricing::price_market(&zeta_group, &greeks, market_index, spot, now)?;
+        zeta_pricing::check_mark_price(
+            greeks.mark_prices[market_index],
+            theo.price,
+            MAX_MARK_PRICE_DEVIATION_PCT,
+        )?;
+        let strike = zeta_group.get_strike(market_index)?;
+        let nav = ctx
+            .accounts
+            .vault_usdc
+            .amount
+            .checked_add(margin_account.balance)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // Max lots such that the vault USDC fully collateralises the puts,
+        // clamped to what the risk limits still allow.
+        let collateral_size: u64 = (ctx.accounts.vault_usdc.amount as u128)
+            .checked_mul(POSITION_PRECISION_DENOMINATOR)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(strike as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow)?;
+        let size = min(
+            collateral_size,
+            get_max_short_size(
+                &ctx.accounts.vault.risk_limits,
+                nav,
+                &margin_account,
+                &zeta_group,
+                market_index,
+            )?,
+        );
+
+        check_risk_limits(
+            &ctx.accounts.vault.risk_limits,
+            nav,
+            &margin_account,
+            &zeta_group,
+            &greeks,
+            spot,
+            market_index,
+            Side::Ask,
+            size,
+        )?;
+        size
+    };
+
     zeta_client::place_order(
         ctx.accounts.zeta_program.clone(),
         ctx.accounts.place_order_cpi_accounts.clone(),
         price,
         size,
         Side::Ask,
-    );
+        None,
+    )
+}
+
+pub fn settle_vault_positions(
+    ctx: Context<SettleVaultPositions>,
+    expiry_index: u8,
+) -> ProgramResult {
+    msg!("SETTLE VAULT POSITIONS");
+    if expiry_index as usize >= ACTIVE_EXPIRIES {
+        return Err(ErrorCode::InvalidExpirySeries.into());
+    }
+    // Settle the expiry the margin account recorded, the zeta group slot may
+    // already hold the next series.
+    let expiry_ts = load_margin_account(&ctx.accounts.margin_account)?
+        .get_settlement_expiry(expiry_index as usize, Clock::get()?.unix_timestamp as u64)?;
+
+    zeta_client::settle_positions(
+        ctx.accounts.zeta_program.clone(),
+        ctx.accounts.settle_positions_cpi_accounts.clone(),
+        vec![ctx.accounts.margin_account.clone()],
+        expiry_ts,
+    )
+}
+
+/// Rejects an option order that would breach the vault's risk limits.
+///
+/// # Arguments
+///
+/// * `nav` - Vault net asset value in USDC (6.dp).
+/// * `market_index` - Market the order is placed on.
+/// * `side` - Order side.
+/// * `size` - Order size in lots (POSITION_PRECISION).
+pub fn check_risk_limits(
+    risk_limits: &RiskLimits,
+    nav: u64,
+    margin_account: &MarginAccount,
+    zeta_group: &ZetaGroup,
+    greeks: &Greeks,
+    spot: u64,
+    market_index: usize,
+    side: Side,
+    size: u64,
+) -> ProgramResult {
+    let product = &zeta_group.products[market_index];
+    let strike = product.strike.get_strike()?;
+    let position = margin_account.positions[market_index];
+    let side_index = get_side_index(side)?;
+
+    // 1. Contracts in this market, including resting orders on the same side.
+    let contracts = position
+        .position
+        .unsigned_abs()
+        .checked_add(position.opening_orders[side_index])
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_add(size)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if contracts > risk_limits.max_contracts_per_market {
+        msg!(
+            "Contracts {} > max contracts per market {}",
+            contracts,
+            risk_limits.max_contracts_per_market
+        );
+        return Err(ErrorCode::MaxContractsExceeded.into());
+    }
+
+    // 2. Strike notional written across all markets.
+    let mut notional = get_short_notional(margin_account, zeta_group)?;
+    if side == Side::Ask {
+        notional = notional
+            .checked_add(
+                (size as u128)
+                    .checked_mul(strike as u128)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(POSITION_PRECISION_DENOMINATOR)
+                    .ok_or(ErrorCode::MathOverflow)?,
+            )
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    let max_notional = get_max_notional(risk_limits, nav)?;
+    if notional > max_notional {
+        msg!("Notional {} > max notional {}", notional, max_notional);
+        return Err(ErrorCode::MaxNotionalExceeded.into());
+    }
+
+    // 3. Margin buffer left after reserving initial margin for the order.
+    if !margin_account.check_margin_requirement(greeks, zeta_group, spot)? {
+        return Err(ErrorCode::InsufficientMarginBuffer.into());
+    }
+    let projection = margin_account.simulate_orders(
+        &[SimulatedOrder {
+            market_index,
+            side,
+            size,
+            price: greeks.mark_prices[market_index],
+            is_fill: false,
+        }],
+        greeks,
+        zeta_group,
+        spot,
+    )?;
+    let buffer = projection.buffer as i128;
+    let min_buffer = (nav as u128)
+        .checked_mul(risk_limits.min_margin_buffer_pct as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(RISK_LIMIT_PRECISION_DENOMINATOR as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if buffer < min_buffer as i128 {
+        msg!(
+            "Margin buffer {} < min margin buffer {}",
+            buffer,
+            min_buffer
+        );
+        return Err(ErrorCode::InsufficientMarginBuffer.into());
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// Strike notional (6.dp) of short positions and resting asks across all markets.
+fn get_short_notional(margin_account: &MarginAccount, zeta_group: &ZetaGroup) -> Result<u128> {
+    margin_account
+        .positions
+        .iter()
+        .enumerate()
+        .try_fold(0u128, |notional, (i, position)| {
+            let strike = match zeta_group.products[i].strike.get_strike() {
+                Ok(strike) => strike,
+                Err(_) => return Ok(notional),
+            };
+            let short_notional = (position.position.min(0).unsigned_abs() as u128)
+                .checked_add(position.opening_orders[1] as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_mul(strike as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(POSITION_PRECISION_DENOMINATOR)
+                .ok_or(ErrorCode::MathOverflow)?;
+            notional
+                .checked_add(short_notional)
+                .ok_or(ErrorCode::MathOverflow.into())
+        })
+}
+
+// Max strike notional (6.dp) the vault may write for a given NAV.
+fn get_max_notional(risk_limits: &RiskLimits, nav: u64) -> Result<u128> {
+    (nav as u128)
+        .checked_mul(risk_limits.max_notional_pct as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(RISK_LIMIT_PRECISION_DENOMINATOR as u128)
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// Largest ask size (POSITION_PRECISION) in `market_index` that stays within
+/// the vault's max contracts per market and max notional.
+pub fn get_max_short_size(
+    risk_limits: &RiskLimits,
+    nav: u64,
+    margin_account: &MarginAccount,
+    zeta_group: &ZetaGroup,
+    market_index: usize,
+) -> Result<u64> {
+    let strike = zeta_group.products[market_index]
+        .strike
+        .get_strike()
+        .map_err(ProgramError::from)?;
+    let position = margin_account.positions[market_index];
+
+    let contracts = position
+        .position
+        .unsigned_abs()
+        .checked_add(position.opening_orders[1])
+        .ok_or(ErrorCode::MathOverflow)?;
+    let contracts_headroom = risk_limits
+        .max_contracts_per_market
+        .saturating_sub(contracts);
+
+    let notional_headroom = get_max_notional(risk_limits, nav)?
+        .saturating_sub(get_short_notional(margin_account, zeta_group)?);
+    let notional_size = notional_headroom
+        .checked_mul(POSITION_PRECISION_DENOMINATOR)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(strike as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(min(contracts_headroom as u128, notional_size) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    const SPOT: u64 = 100_000_000; // $100
+    const LOT: u64 = 1_000; // POSITION_PRECISION
+    const NAV: u64 = 1_000_000_000; // $1000
+
+    // A $100 put at market index 0 with a 10% short initial margin, and a
+    // margin account holding the whole NAV.
+    fn setup() -> (Box<ZetaGroup>, Box<Greeks>, Box<MarginAccount>) {
+        let mut zeta_group = Box::new(ZetaGroup::zeroed());
+        let greeks = Box::new(Greeks::zeroed());
+        let mut margin_account = Box::new(MarginAccount::zeroed());
+        zeta_group.products[0] = Product {
+            market: Pubkey::default(),
+            strike: Strike {
+                is_set: true,
+                value: SPOT,
+            },
+            dirty: false,
+            kind: Kind::Put,
+        };
+        zeta_group
+            .margin_parameters
+            .option_spot_percentage_short_initial = 10_000_000;
+        zeta_group.margin_parameters.option_short_put_cap_percentage = 100_000_000;
+        margin_account.balance = NAV;
+        (zeta_group, greeks, margin_account)
+    }
+
+    // Selling one lot sits exactly at every limit: 1 contract, $100 notional
+    // (10% of NAV) and $990 buffer (99% of NAV) after $10 initial margin.
+    fn risk_limits() -> RiskLimits {
+        RiskLimits {
+            max_notional_pct: 10_000_000,
+            max_contracts_per_market: LOT,
+            min_margin_buffer_pct: 99_000_000,
+            max_greeks_age: 0,
+        }
+    }
+
+    fn check(
+        risk_limits: &RiskLimits,
+        margin_account: &MarginAccount,
+        side: Side,
+    ) -> ProgramResult {
+        let (zeta_group, greeks, _) = setup();
+        check_risk_limits(
+            risk_limits,
+            NAV,
+            margin_account,
+            &zeta_group,
+            &greeks,
+            SPOT,
+            0,
+            side,
+            LOT,
+        )
+    }
+
+    #[test]
+    fn accepts_order_at_limits() {
+        let (_, _, margin_account) = setup();
+        assert!(check(&risk_limits(), &margin_account, Side::Ask).is_ok());
+    }
+
+    #[test]
+    fn rejects_max_contracts_breach() {
+        let (_, _, margin_account) = setup();
+        let mut risk_limits = risk_limits();
+        risk_limits.max_contracts_per_market = LOT - 1;
+        assert_eq!(
+            check(&risk_limits, &margin_account, Side::Ask),
+            Err(ErrorCode::MaxContractsExceeded.into())
+        );
+    }
+
+    #[test]
+    fn rejects_max_notional_breach() {
+        let (_, _, margin_account) = setup();
+        let mut risk_limits = risk_limits();
+        risk_limits.max_notional_pct -= 1;
+        assert_eq!(
+            check(&risk_limits, &margin_account, Side::Ask),
+            Err(ErrorCode::MaxNotionalExceeded.into())
+        );
+    }
+
+    #[test]
+    fn rejects_margin_buffer_breach() {
+        let (_, _, margin_account) = setup();
+        let mut risk_limits = risk_limits();
+        risk_limits.min_margin_buffer_pct += 1;
+        assert_eq!(
+            check(&risk_limits, &margin_account, Side::Ask),
+            Err(ErrorCode::InsufficientMarginBuffer.into())
+        );
+    }
+
+    #[test]
+    fn rejects_uninitialized_side() {
+        let (_, _, margin_account) = setup();
+        assert!(check(&risk_limits(), &margin_account, Side::Uninitialized).is_err());
+    }
+
+    #[test]
+    fn sums_short_positions_and_resting_asks() {
+        let (zeta_group, _, mut margin_account) = setup();
+        margin_account.positions[0] = Position {
+            position: -2 * (LOT as i64),
+            cost_of_trades: 0,
+            closing_orders: 0,
+            opening_orders: [LOT, LOT],
+        };
+        // Unset strike, not counted.
+        margin_account.positions[1] = Position {
+            position: -(LOT as i64),
+            cost_of_trades: 0,
+            closing_orders: 0,
+            opening_orders: [0, 0],
+        };
+        assert_eq!(
+            get_short_notional(&margin_account, &zeta_group).unwrap(),
+            3 * SPOT as u128
+        );
+    }
+
+    #[test]
+    fn short_notional_of_min_position_does_not_overflow() {
+        let (zeta_group, _, mut margin_account) = setup();
+        margin_account.positions[0].position = i64::MIN;
+        assert!(get_short_notional(&margin_account, &zeta_group).is_ok());
+    }
+
+    #[test]
+    fn clamps_short_size_to_limits() {
+        let (zeta_group, _, mut margin_account) = setup();
+        let mut risk_limits = risk_limits();
+        // Notional allows 1 lot, contracts allow 3.
+        risk_limits.max_contracts_per_market = 3 * LOT;
+        assert_eq!(
+            get_max_short_size(&risk_limits, NAV, &margin_account, &zeta_group, 0).unwrap(),
+            LOT
+        );
+
+        // Notional allows 3 lots, contracts allow 2 with one already short.
+        risk_limits.max_notional_pct *= 4;
+        margin_account.positions[0].position = -(LOT as i64);
+        assert_eq!(
+            get_max_short_size(&risk_limits, NAV, &margin_account, &zeta_group, 0).unwrap(),
+            2 * LOT
+        );
+
+        // No headroom left.
+        risk_limits.max_contracts_per_market = LOT;
+        assert_eq!(
+            get_max_short_size(&risk_limits, NAV, &margin_account, &zeta_group, 0).unwrap(),
+            0
+        );
+    }
+}
@@ -198,7 +198,7 @@ describe("vault", () => {
       vaultName,
       vaultLamports,
       bumps,
-      epochTimes,
+      { manual: { epochTimes } },
       {
         accounts: {
           vaultAdmin: vaultAdmin.publicKey,
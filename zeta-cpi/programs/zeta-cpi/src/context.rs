@@ -1,4 +1,6 @@
-use crate::zeta_context::*;
+use crate::oracle::ManualPrice;
+use zeta_sdk::zeta_context::nested::*;
+use zeta_sdk::zeta_context::*;
 use crate::*;
 
 // CPI Program Context
@@ -28,6 +30,18 @@ pub struct InitializeOpenOrdersCaller<'info> {
     pub initialize_open_orders_cpi_accounts: InitializeOpenOrders<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CloseOpenOrdersCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub close_open_orders_cpi_accounts: CloseOpenOrders<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMarginAccountCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub close_margin_account_cpi_accounts: CloseMarginAccount<'info>,
+}
+
 #[derive(Accounts)]
 pub struct PlaceOrderCaller<'info> {
     pub zeta_program: AccountInfo<'info>,
@@ -40,6 +54,44 @@ pub struct CancelOrderCaller<'info> {
     pub cancel_order_cpi_accounts: CancelOrder<'info>,
 }
 
+// Margin accounts to settle are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct SettlePositionsCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub settle_positions_cpi_accounts: SettlePositions<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub liquidate_cpi_accounts: Liquidate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceDepositAccountCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub initialize_insurance_deposit_cpi_accounts: InitializeInsuranceDepositAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositInsuranceVaultCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub deposit_insurance_vault_cpi_accounts: DepositInsuranceVault<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawInsuranceVaultCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub withdraw_insurance_vault_cpi_accounts: WithdrawInsuranceVault<'info>,
+}
+
+// Open orders and margin accounts referenced by the queue are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct CrankEventQueueCaller<'info> {
+    pub zeta_program: AccountInfo<'info>,
+    pub crank_event_queue_cpi_accounts: CrankEventQueue<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ReadProgramData<'info> {
     pub state: AccountInfo<'info>,
@@ -48,3 +100,19 @@ pub struct ReadProgramData<'info> {
     pub greeks: AccountInfo<'info>,
     pub oracle: AccountInfo<'info>,
 }
+
+#[derive(Accounts)]
+pub struct InitializeManualOracle<'info> {
+    #[account(init, payer = authority)]
+    pub manual_oracle: Account<'info, ManualPrice>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetManualOraclePrice<'info> {
+    #[account(mut, has_one = authority)]
+    pub manual_oracle: Account<'info, ManualPrice>,
+    pub authority: Signer<'info>,
+}
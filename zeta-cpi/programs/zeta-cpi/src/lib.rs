@@ -1,20 +1,26 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
 use rust_decimal::prelude::*;
 
 pub mod context;
-pub mod pyth_client;
-pub mod zeta_account;
-pub mod zeta_client;
-pub mod zeta_constants;
-pub mod zeta_context;
-pub mod zeta_utils;
+pub mod oracle;
 use crate::context::*;
-use crate::zeta_account::*;
-use crate::zeta_constants::*;
-use crate::zeta_utils::*;
+use crate::oracle::*;
+use zeta_sdk::oracle::*;
+use zeta_sdk::zeta_account::*;
+use zeta_sdk::zeta_client;
+use zeta_sdk::zeta_constants::*;
+use zeta_sdk::zeta_context::PlaceOrder;
+use zeta_sdk::zeta_portfolio::*;
+use zeta_sdk::zeta_snapshot::*;
+use zeta_sdk::zeta_utils::*;
+use zeta_sdk::{wrap_error, ErrorCode, Result};
 
 declare_id!("7Aqh4kCtp3rdtHSk1mFXswyw37z9Ldad7vmgrbVD2h9J");
 
+// Max age in seconds of the greeks mark prices are read from.
+pub const GREEKS_MAX_AGE: u64 = 60;
+
 #[program]
 pub mod zeta_cpi {
     use super::*;
@@ -49,6 +55,20 @@ pub mod zeta_cpi {
         )
     }
 
+    pub fn close_open_orders(ctx: Context<CloseOpenOrdersCaller>) -> ProgramResult {
+        zeta_client::close_open_orders(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.close_open_orders_cpi_accounts.clone(),
+        )
+    }
+
+    pub fn close_margin_account(ctx: Context<CloseMarginAccountCaller>) -> ProgramResult {
+        zeta_client::close_margin_account(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.close_margin_account_cpi_accounts.clone(),
+        )
+    }
+
     pub fn place_order(
         ctx: Context<PlaceOrderCaller>,
         price: u64,
@@ -56,6 +76,7 @@ pub mod zeta_cpi {
         side: Side,
         client_order_id: Option<u64>,
     ) -> ProgramResult {
+        validate_market_tradeable(&ctx.accounts.place_order_cpi_accounts)?;
         zeta_client::place_order(
             ctx.accounts.zeta_program.clone(),
             ctx.accounts.place_order_cpi_accounts.clone(),
@@ -66,6 +87,26 @@ pub mod zeta_cpi {
         )
     }
 
+    pub fn place_order_v2(
+        ctx: Context<PlaceOrderCaller>,
+        price: u64,
+        size: u64,
+        side: Side,
+        order_type: OrderType,
+        client_order_id: Option<u64>,
+    ) -> ProgramResult {
+        validate_market_tradeable(&ctx.accounts.place_order_cpi_accounts)?;
+        zeta_client::place_order_v2(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.place_order_cpi_accounts.clone(),
+            price,
+            size,
+            side,
+            order_type,
+            client_order_id,
+        )
+    }
+
     pub fn cancel_order(
         ctx: Context<CancelOrderCaller>,
         side: Side,
@@ -79,9 +120,168 @@ pub mod zeta_cpi {
         )
     }
 
+    pub fn cancel_order_by_client_order_id(
+        ctx: Context<CancelOrderCaller>,
+        client_order_id: u64,
+    ) -> ProgramResult {
+        zeta_client::cancel_order_by_client_order_id(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.cancel_order_cpi_accounts.clone(),
+            client_order_id,
+        )
+    }
+
+    pub fn cancel_all_orders(ctx: Context<CancelOrderCaller>) -> ProgramResult {
+        zeta_client::cancel_all_orders(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.cancel_order_cpi_accounts.clone(),
+        )
+    }
+
+    pub fn settle_positions<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SettlePositionsCaller<'info>>,
+        expiry_index: u8,
+    ) -> ProgramResult {
+        if expiry_index as usize >= ACTIVE_EXPIRIES {
+            return wrap_error!(Err(ErrorCode::InvalidExpiryIndex.into()));
+        }
+        // The zeta group slot may already hold a newer series, settle the
+        // expiry the margin accounts themselves recorded.
+        let now = Clock::get()?.unix_timestamp as u64;
+        let mut expiry_ts = None;
+        for account in ctx.remaining_accounts.iter() {
+            let margin_account = load_margin_account(account)?;
+            let account_expiry_ts =
+                margin_account.get_settlement_expiry(expiry_index as usize, now)?;
+            if *expiry_ts.get_or_insert(account_expiry_ts) != account_expiry_ts {
+                return wrap_error!(Err(ErrorCode::InvalidSettlementAccount.into()));
+            }
+        }
+        let expiry_ts = expiry_ts.ok_or(ErrorCode::InvalidSettlementAccount)?;
+
+        zeta_client::settle_positions(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.settle_positions_cpi_accounts.clone(),
+            ctx.remaining_accounts.to_vec(),
+            expiry_ts,
+        )
+    }
+
+    pub fn liquidate(ctx: Context<LiquidateCaller>, size: u64) -> ProgramResult {
+        {
+            let cpi_accounts = &ctx.accounts.liquidate_cpi_accounts;
+            let zeta_group = load_zeta_group(&cpi_accounts.zeta_group)?;
+            let greeks = load_greeks(&cpi_accounts.greeks)?;
+            let margin_account = load_margin_account(&cpi_accounts.liquidated_margin_account)?;
+            let spot = load_zeta_group_oracle(&cpi_accounts.oracle, &zeta_group)?
+                .get_price(PLATFORM_PRECISION)?;
+            if !margin_account.is_liquidatable(&greeks, &zeta_group, spot)? {
+                return wrap_error!(Err(ErrorCode::AccountNotLiquidatable.into()));
+            }
+        }
+
+        zeta_client::liquidate(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.liquidate_cpi_accounts.clone(),
+            size,
+        )
+    }
+
+    pub fn initialize_insurance_deposit_account(
+        ctx: Context<InitializeInsuranceDepositAccountCaller>,
+    ) -> ProgramResult {
+        zeta_client::initialize_insurance_deposit_account(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.initialize_insurance_deposit_cpi_accounts.clone(),
+        )
+    }
+
+    pub fn deposit_insurance_vault(
+        ctx: Context<DepositInsuranceVaultCaller>,
+        amount: u64,
+    ) -> ProgramResult {
+        zeta_client::deposit_insurance_vault(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.deposit_insurance_vault_cpi_accounts.clone(),
+            amount,
+        )
+    }
+
+    pub fn withdraw_insurance_vault(
+        ctx: Context<WithdrawInsuranceVaultCaller>,
+        percentage_amount: u64,
+    ) -> ProgramResult {
+        zeta_client::withdraw_insurance_vault(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.withdraw_insurance_vault_cpi_accounts.clone(),
+            percentage_amount,
+        )
+    }
+
+    pub fn crank_event_queue<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CrankEventQueueCaller<'info>>,
+    ) -> ProgramResult {
+        zeta_client::crank_event_queue(
+            ctx.accounts.zeta_program.clone(),
+            ctx.accounts.crank_event_queue_cpi_accounts.clone(),
+            ctx.remaining_accounts.to_vec(),
+        )
+    }
+
+    pub fn initialize_manual_oracle(
+        ctx: Context<InitializeManualOracle>,
+        price: u64,
+        confidence: u64,
+        expo: i32,
+    ) -> ProgramResult {
+        let manual_oracle = &mut ctx.accounts.manual_oracle;
+        manual_oracle.authority = ctx.accounts.authority.key();
+        manual_oracle.price = price;
+        manual_oracle.confidence = confidence;
+        manual_oracle.expo = expo;
+        manual_oracle.publish_time = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    pub fn set_manual_oracle_price(
+        ctx: Context<SetManualOraclePrice>,
+        price: u64,
+        confidence: u64,
+    ) -> ProgramResult {
+        let manual_oracle = &mut ctx.accounts.manual_oracle;
+        manual_oracle.price = price;
+        manual_oracle.confidence = confidence;
+        manual_oracle.publish_time = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // Same data as `read_program_data`, returned to the caller as a borsh
+    // encoded `MarketSnapshot` instead of logged.
+    pub fn read_market_snapshot(ctx: Context<ReadProgramData>) -> ProgramResult {
+        let zeta_group = load_zeta_group(&ctx.accounts.zeta_group)?;
+        let greeks = load_greeks(&ctx.accounts.greeks)?;
+        let margin_account = load_margin_account(&ctx.accounts.margin_account)?;
+        // The margin figures are priced off this, only trust the group's Pyth feed.
+        let oracle_price = load_zeta_group_oracle(&ctx.accounts.oracle, &zeta_group)?
+            .get_price(PLATFORM_PRECISION)?;
+        let clock = Clock::get()?;
+        // Marks and margins of every live series are only as fresh as its greeks.
+        greeks.check_live_freshness(&zeta_group, clock.unix_timestamp as u64, GREEKS_MAX_AGE)?;
+
+        let snapshot = get_market_snapshot(
+            &zeta_group,
+            &greeks,
+            &margin_account,
+            oracle_price,
+            zeta_group.front_expiry_index as usize,
+            clock.unix_timestamp as u64,
+        )?;
+        set_return_data(&snapshot.try_to_vec()?);
+        Ok(())
+    }
+
     pub fn read_program_data(ctx: Context<ReadProgramData>) -> ProgramResult {
-        let zeta_group =
-            deserialize_account_info_zerocopy::<ZetaGroup>(&ctx.accounts.zeta_group).unwrap();
+        let zeta_group = load_zeta_group(&ctx.accounts.zeta_group)?;
 
         // Get the data for the front expiration.
         let expiry_index = zeta_group.front_expiry_index as usize;
@@ -115,8 +315,10 @@ pub mod zeta_cpi {
             // i.e `product == &zeta_group.products[product_index]`
             let market_index = get_products_slice_market_index(expiry_index, i);
 
-            // Strike has 6 decimals of precision.
-            let strike = product.strike.get_strike()?;
+            // Strike has 6 decimals of precision, 0 for the future.
+            let strike = product
+                .get_margin_strike()
+                .ok_or(ErrorCode::ProductStrikeUninitialized)?;
 
             // The serum market this product trades on.
             let market = product.market;
@@ -130,16 +332,37 @@ pub mod zeta_cpi {
             ));
         }
 
-        // This returns the oracle price as a fixed point integer with 6 decimals of precision
-        let oracle_price = get_native_oracle_price(&ctx.accounts.oracle);
-        msg!("Oracle price {:?}", oracle_price);
+        // This returns the oracle price as a fixed point integer with 6 decimals of precision.
+        // The oracle can either be a Pyth price account or a manual price account owned by this program.
+        let oracle = load_oracle(&ctx.accounts.oracle)?;
+        let oracle_price = oracle.get_price(PLATFORM_PRECISION)?;
+        msg!(
+            "Oracle price {:?}, confidence {:?}, publish time {:?}",
+            oracle_price,
+            oracle.get_confidence(PLATFORM_PRECISION)?,
+            oracle.get_publish_time()?
+        );
 
         // Get the mark price and greek data for the first product in the expiry series.
         // This happens to be the lowest strike call.
         let product_index = 0;
         let market_index = get_products_slice_market_index(expiry_index, product_index);
 
-        let greeks = deserialize_account_info_zerocopy::<Greeks>(&ctx.accounts.greeks).unwrap();
+        let greeks = load_greeks(&ctx.accounts.greeks)?;
+
+        // Mark prices are only as fresh as the last pricing update of the series,
+        // reject them past GREEKS_MAX_AGE.
+        greeks.check_live_freshness(
+            &zeta_group,
+            Clock::get()?.unix_timestamp as u64,
+            GREEKS_MAX_AGE,
+        )?;
+        msg!(
+            "Greeks updated at {}, retreat expiration {}",
+            greeks.update_timestamp[expiry_index],
+            greeks.retreat_expiration_timestamp[expiry_index]
+        );
+
         let market_mark_prices = greeks.get_mark_prices_slice(expiry_index)[product_index];
         let market_product_greeks = greeks.get_product_greeks_slice(expiry_index)[product_index];
 
@@ -152,9 +375,7 @@ pub mod zeta_cpi {
             Decimal::from(market_product_greeks.volatility)
         ));
 
-        let margin_account =
-            deserialize_account_info_zerocopy::<MarginAccount>(&ctx.accounts.margin_account)
-                .unwrap();
+        let margin_account = load_margin_account(&ctx.accounts.margin_account)?;
 
         msg!("Margin account balance: {:?}", margin_account.balance);
 
@@ -170,12 +391,12 @@ pub mod zeta_cpi {
         );
 
         let initial_margin_requirement =
-            margin_account.get_initial_margin(&greeks, &zeta_group, oracle_price);
+            margin_account.get_initial_margin(&greeks, &zeta_group, oracle_price)?;
         let maintenance_margin_requirement =
-            margin_account.get_maintenance_margin(&greeks, &zeta_group, oracle_price);
+            margin_account.get_maintenance_margin(&greeks, &zeta_group, oracle_price)?;
         let total_margin_requirement = initial_margin_requirement
             .checked_add(maintenance_margin_requirement)
-            .unwrap();
+            .ok_or(ErrorCode::MathOverflow)?;
 
         msg!(
             "Margin account: Initial: {}, Maintenance: {}, Total: {}",
@@ -184,24 +405,26 @@ pub mod zeta_cpi {
             total_margin_requirement
         );
 
+        // Net greeks of the margin account across all live expiries.
+        let portfolio = get_portfolio_greeks(&margin_account, &zeta_group, &greeks, oracle_price)?;
+        msg!(&format!(
+            "Portfolio: Delta = {}, Vega = {:?}, Notional = {}",
+            portfolio.total.delta, portfolio.total.vega, portfolio.total.notional
+        ));
+
         Ok(())
     }
 }
 
-#[error]
-pub enum ErrorCode {
-    #[msg("Account not mutable")]
-    AccountNotMutable,
-    #[msg("Unsupported kind")]
-    UnsupportedKind,
-    #[msg("Product strike uninitialized")]
-    ProductStrikeUninitialized,
-    #[msg("Invalid product market key")]
-    InvalidProductMarketKey,
-    #[msg("Market not live")]
-    MarketNotLive,
-    #[msg("Product dirty")]
-    ProductDirty,
-    #[msg("Invalid option kind, must be Call or Put")]
-    InvalidOptionKind,
+// Rejects orders on markets Zeta would not accept them on before making the CPI.
+fn validate_market_tradeable(cpi_accounts: &PlaceOrder) -> ProgramResult {
+    let zeta_group = load_zeta_group(&cpi_accounts.zeta_group)?;
+    let market_index =
+        zeta_group.get_product_index_by_key(cpi_accounts.market_accounts.market.key)?;
+    let clock = Clock::get()?;
+    zeta_group.validate_series_tradeable(
+        zeta_group.get_expiry_index_by_product_index(market_index),
+        clock.unix_timestamp as u64,
+    )?;
+    Ok(())
 }
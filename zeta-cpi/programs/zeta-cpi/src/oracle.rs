@@ -0,0 +1,71 @@
+use crate::*;
+use zeta_sdk::pyth_client::{pyth_program, PriceValidationConfig};
+
+// Pyth checks applied to prices read from a zeta group's oracle.
+pub const ORACLE_MAX_SLOT_AGE: u64 = 25; // ~10 seconds
+pub const ORACLE_MAX_CONFIDENCE_PCT: u64 = 2_000_000; // 2%, NATIVE_PRECISION_DENOMINATOR
+
+/// Loads the oracle behind `oracle`, a `ManualPrice` account if it is owned
+/// by this program or a Pyth price account if it is owned by the Pyth program.
+pub fn load_oracle<'a>(oracle: &'a AccountInfo) -> Result<Box<dyn Oracle + 'a>> {
+    if oracle.owner == &crate::ID {
+        let data = oracle.try_borrow_data()?;
+        let manual_price = ManualPrice::try_deserialize(&mut &data[..])?;
+        Ok(Box::new(manual_price))
+    } else if oracle.owner == &pyth_program::ID {
+        Ok(Box::new(PythOracle::load(oracle)?))
+    } else {
+        msg!("Oracle owner = {}", oracle.owner);
+        wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()))
+    }
+}
+
+/// Loads `zeta_group`'s Pyth oracle, rejecting any other account and prices
+/// that fail the status, staleness and confidence checks. Use this instead of
+/// `load_oracle` wherever the price gates an action, since anyone can create a
+/// `ManualPrice`.
+pub fn load_zeta_group_oracle<'a>(
+    oracle: &'a AccountInfo,
+    zeta_group: &ZetaGroup,
+) -> Result<PythOracle<'a>> {
+    if oracle.key != &zeta_group.oracle {
+        msg!(
+            "Oracle = {}, zeta group oracle = {}",
+            oracle.key,
+            zeta_group.oracle
+        );
+        return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()));
+    }
+    PythOracle::load_validated(
+        oracle,
+        &PriceValidationConfig {
+            max_slot_age: ORACLE_MAX_SLOT_AGE,
+            max_confidence_pct: ORACLE_MAX_CONFIDENCE_PCT,
+        },
+    )
+}
+
+/// Program-owned price account updated by its authority.
+#[account]
+#[derive(Default)]
+pub struct ManualPrice {
+    pub authority: Pubkey,
+    pub price: u64,
+    pub confidence: u64,
+    pub expo: i32, // Price exponent, same convention as Pyth i.e. -6 => 6.dp
+    pub publish_time: i64,
+}
+
+impl Oracle for ManualPrice {
+    fn get_price(&self, precision: u32) -> Result<u64> {
+        scale_to_precision(self.price, self.expo, precision)
+    }
+
+    fn get_confidence(&self, precision: u32) -> Result<u64> {
+        scale_to_precision(self.confidence, self.expo, precision)
+    }
+
+    fn get_publish_time(&self) -> Result<i64> {
+        Ok(self.publish_time)
+    }
+}
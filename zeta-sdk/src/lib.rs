@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use rust_decimal::prelude::*;
+
+pub mod oracle;
+pub mod pyth_client;
+pub mod serum_event_queue;
+pub mod serum_market;
+pub mod serum_open_orders;
+pub mod zeta_account;
+pub mod zeta_client;
+pub mod zeta_constants;
+pub mod zeta_context;
+pub mod zeta_pda;
+pub mod zeta_portfolio;
+pub mod zeta_pricing;
+pub mod zeta_selection;
+pub mod zeta_snapshot;
+pub mod zeta_utils;
+pub mod zeta_volatility;
+use crate::zeta_account::*;
+use crate::zeta_constants::*;
+use crate::zeta_utils::*;
+
+// Zeta program id, owner of the account layouts defined in this crate.
+#[cfg(feature = "mainnet")]
+declare_id!("ZETAxsqBRek56DhiGXrn75yj2NHU3aYUnxvHXpkf3aD");
+#[cfg(not(feature = "mainnet"))]
+declare_id!("BG3oRikW8d16YjUEmX3ZxHm9SiJzrGtMhsSR8aCw1Cd7");
+
+// Offset away from the default custom error range (300) used by the programs
+// embedding this crate and from Zeta's own errors (6000+), so a failed CPI
+// can be told apart from an SDK error.
+#[error(offset = 9000)]
+pub enum ErrorCode {
+    #[msg("Account not mutable")]
+    AccountNotMutable,
+    #[msg("Unsupported kind")]
+    UnsupportedKind,
+    #[msg("Product strike uninitialized")]
+    ProductStrikeUninitialized,
+    #[msg("Invalid product market key")]
+    InvalidProductMarketKey,
+    #[msg("Market not live")]
+    MarketNotLive,
+    #[msg("Product dirty")]
+    ProductDirty,
+    #[msg("Invalid option kind, must be Call or Put")]
+    InvalidOptionKind,
+    #[msg("No live market matches the selection criteria")]
+    NoMatchingMarket,
+    #[msg("Invalid oracle account")]
+    InvalidOracleAccount,
+    #[msg("Oracle price is not trading")]
+    OracleNotTrading,
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    #[msg("Oracle confidence interval is too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Account is not owned by the Zeta program")]
+    InvalidAccountOwner,
+    #[msg("Invalid account discriminator")]
+    InvalidAccountDiscriminator,
+    #[msg("Invalid account length")]
+    InvalidAccountLength,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Invalid side")]
+    InvalidSide,
+    #[msg("Spot price is zero")]
+    ZeroSpot,
+    #[msg("Market index out of range")]
+    InvalidMarketIndex,
+    #[msg("Invalid pricing inputs")]
+    InvalidPricingInputs,
+    #[msg("Mark price deviates too far from theoretical price")]
+    MarkPriceDeviation,
+    #[msg("Invalid open orders account")]
+    InvalidOpenOrders,
+    #[msg("Expiry series has not expired")]
+    SeriesNotExpired,
+    #[msg("Margin account is not liquidatable")]
+    AccountNotLiquidatable,
+    #[msg("Order price must be a non-zero multiple of the tick size")]
+    InvalidOrderPrice,
+    #[msg("Order size must be a non-zero multiple of the lot size")]
+    InvalidOrderSize,
+    #[msg("Invalid event queue account")]
+    InvalidEventQueue,
+    #[msg("Margin account has open positions")]
+    MarginAccountHasPositions,
+    #[msg("Margin account has active orders")]
+    MarginAccountHasActiveOrders,
+    #[msg("Invalid market snapshot data")]
+    InvalidSnapshot,
+    #[msg("Greeks are stale")]
+    StaleGreeks,
+    #[msg("Expiry index out of range")]
+    InvalidExpiryIndex,
+    #[msg("Settlement account does not match the expiry")]
+    InvalidSettlementAccount,
+    #[msg("Invalid serum market account")]
+    InvalidSerumMarket,
+}
@@ -0,0 +1,101 @@
+use crate::*;
+use anchor_lang::solana_program::clock::DEFAULT_MS_PER_SLOT;
+use std::cell::Ref;
+use std::convert::TryInto;
+
+/// Oracle Interface
+/// Abstracts over price sources so strategies can swap a live Pyth feed
+/// for a program-owned manual price, e.g. on devnet or in tests.
+pub trait Oracle {
+    /// Returns the price as a fixed point integer with `precision` decimals.
+    fn get_price(&self, precision: u32) -> Result<u64>;
+
+    /// Returns the confidence interval with `precision` decimals.
+    fn get_confidence(&self, precision: u32) -> Result<u64>;
+
+    /// Returns the unix timestamp the price was published at.
+    fn get_publish_time(&self) -> Result<i64>;
+}
+
+pub struct PythOracle<'a> {
+    price: Ref<'a, pyth_client::Price>,
+    clock: Clock,
+}
+
+impl<'a> PythOracle<'a> {
+    pub fn load(oracle: &'a AccountInfo) -> Result<Self> {
+        Ok(PythOracle {
+            price: pyth_client::Price::load_checked(oracle)?,
+            clock: Clock::get()?,
+        })
+    }
+
+    /// Same as `load` but also applies the status, staleness and
+    /// confidence checks of `Price::get_validated_price`.
+    pub fn load_validated(
+        oracle: &'a AccountInfo,
+        config: &pyth_client::PriceValidationConfig,
+    ) -> Result<Self> {
+        let pyth_oracle = Self::load(oracle)?;
+        pyth_oracle
+            .price
+            .get_validated_price(pyth_oracle.clock.slot, config)?;
+        Ok(pyth_oracle)
+    }
+}
+
+impl<'a> Oracle for PythOracle<'a> {
+    fn get_price(&self, precision: u32) -> Result<u64> {
+        let price = self.price.agg.price;
+        if price <= 0 {
+            return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()));
+        }
+        self.price.scale_to_precision(price as u64, precision)
+    }
+
+    fn get_confidence(&self, precision: u32) -> Result<u64> {
+        self.price
+            .scale_to_precision(self.price.agg.conf, precision)
+    }
+
+    // Pyth price accounts only record slots, so the publish time is
+    // estimated from the slot lag behind the current clock.
+    fn get_publish_time(&self) -> Result<i64> {
+        let slot_lag = self.clock.slot.saturating_sub(self.price.valid_slot);
+        let lag_seconds = slot_lag
+            .checked_mul(DEFAULT_MS_PER_SLOT)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(1_000)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let lag_seconds: i64 = lag_seconds
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow)?;
+        self.clock
+            .unix_timestamp
+            .checked_sub(lag_seconds)
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+}
+
+/// Scales `value` at exponent `expo` to `precision` decimals.
+pub fn scale_to_precision(value: u64, expo: i32, precision: u32) -> Result<u64> {
+    let precision_scale = 10u128
+        .checked_pow(precision)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let expo_scale = 10u128
+        .checked_pow(expo.unsigned_abs())
+        .ok_or(ErrorCode::MathOverflow)?;
+    let scaled = if expo <= 0 {
+        (value as u128)
+            .checked_mul(precision_scale)
+            .and_then(|v| v.checked_div(expo_scale))
+    } else {
+        (value as u128)
+            .checked_mul(precision_scale)
+            .and_then(|v| v.checked_mul(expo_scale))
+    };
+    match scaled.and_then(|v| v.try_into().ok()) {
+        Some(scaled) => Ok(scaled),
+        None => wrap_error!(Err(ErrorCode::InvalidOracleAccount.into())),
+    }
+}
@@ -0,0 +1,355 @@
+// Taken from https://github.com/project-serum/anchor/tree/master/examples/pyth
+use crate::*;
+use anchor_lang::prelude::AccountInfo;
+use bytemuck::{Pod, Zeroable};
+use std::cell::Ref;
+use std::convert::TryInto;
+
+pub const MAGIC: u32 = 0xa1b2c3d4;
+pub const VERSION_2: u32 = 2;
+pub const VERSION: u32 = VERSION_2;
+pub const ACCOUNT_TYPE_PRICE: u32 = 3;
+
+/// Pyth oracle program, owner of every price account.
+pub mod pyth_program {
+    use anchor_lang::declare_id;
+    #[cfg(feature = "mainnet")]
+    declare_id!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+    #[cfg(not(feature = "mainnet"))]
+    declare_id!("gSbePebfvPy7tRqimPoVecS2UvBvYPay5XMoJXFDSnc");
+}
+
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+pub struct AccKey {
+    pub val: [u8; 32],
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[repr(C)]
+pub enum PriceStatus {
+    Unknown,
+    Trading,
+    Halted,
+    Auction,
+}
+
+impl PriceStatus {
+    /// Maps a raw status read from account data, rejecting unknown values.
+    pub fn from_u32(status: u32) -> Result<Self> {
+        match status {
+            0 => Ok(PriceStatus::Unknown),
+            1 => Ok(PriceStatus::Trading),
+            2 => Ok(PriceStatus::Halted),
+            3 => Ok(PriceStatus::Auction),
+            _ => {
+                msg!("Invalid oracle status = {}", status);
+                wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()))
+            }
+        }
+    }
+}
+
+impl Default for PriceStatus {
+    fn default() -> Self {
+        PriceStatus::Trading
+    }
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub enum CorpAction {
+    NoCorpAct,
+}
+
+impl Default for CorpAction {
+    fn default() -> Self {
+        CorpAction::NoCorpAct
+    }
+}
+
+// Enum fields are stored as their raw u32 values so that any account bytes
+// are a valid `Price` and can be cast without undefined behaviour.
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+pub struct PriceInfo {
+    pub price: i64,
+    pub conf: u64,
+    pub status: u32,   // PriceStatus, read through `get_status`.
+    pub corp_act: u32, // CorpAction.
+    pub pub_slot: u64,
+}
+
+impl PriceInfo {
+    pub fn get_status(&self) -> Result<PriceStatus> {
+        PriceStatus::from_u32(self.status)
+    }
+}
+
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+pub struct PriceComp {
+    publisher: AccKey,
+    agg: PriceInfo,
+    latest: PriceInfo,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub enum PriceType {
+    Unknown,
+    Price,
+    TWAP,
+    Volatility,
+}
+
+impl Default for PriceType {
+    fn default() -> Self {
+        PriceType::Price
+    }
+}
+
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+pub struct Price {
+    pub magic: u32, // Pyth magic number.
+    pub ver: u32,   // Program version.
+    pub atype: u32, // Account type.
+    pub size: u32,  // Price account size.
+    pub ptype: u32, // Price or calculation type (PriceType).
+    pub expo: i32,  // Price exponent.
+    pub num: u32,   // Number of component prices.
+    pub unused: u32,
+    pub curr_slot: u64,        // Currently accumulating price slot.
+    pub valid_slot: u64,       // Valid slot-time of agg. price.
+    pub twap: i64,             // Time-weighted average price.
+    pub avol: u64,             // Annualized price volatility.
+    pub drv0: i64,             // Space for future derived values.
+    pub drv1: i64,             // Space for future derived values.
+    pub drv2: i64,             // Space for future derived values.
+    pub drv3: i64,             // Space for future derived values.
+    pub drv4: i64,             // Space for future derived values.
+    pub drv5: i64,             // Space for future derived values.
+    pub prod: AccKey,          // Product account key.
+    pub next: AccKey,          // Next Price account in linked list.
+    pub agg_pub: AccKey,       // Quoter who computed last aggregate price.
+    pub agg: PriceInfo,        // Aggregate price info.
+    pub comp: [PriceComp; 32], // Price components one per quoter.
+}
+
+impl Price {
+    /// Read-only load that validates the account is a Pyth price account
+    /// (magic, version, account type and size) instead of panicking.
+    pub fn load_checked<'a>(price_feed: &'a AccountInfo) -> Result<Ref<'a, Price>> {
+        if price_feed.owner != &pyth_program::ID {
+            msg!("Oracle owner = {}", price_feed.owner);
+            return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()));
+        }
+        let data = price_feed.try_borrow_data()?;
+        let size = std::mem::size_of::<Price>();
+        if data.len() < size {
+            return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()));
+        }
+        if let Err(e) = bytemuck::try_from_bytes::<Price>(&data[..size]) {
+            msg!("Invalid oracle data: {:?}", e);
+            return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()));
+        }
+        // Length and alignment were checked above, so this can't panic.
+        let state = Ref::map(data, |data| bytemuck::from_bytes::<Price>(&data[..size]));
+        if state.magic != MAGIC || state.ver != VERSION || state.atype != ACCOUNT_TYPE_PRICE {
+            msg!(
+                "Invalid oracle: magic = {}, version = {}, account type = {}",
+                state.magic,
+                state.ver,
+                state.atype
+            );
+            return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()));
+        }
+        Ok(state)
+    }
+
+    /// Returns the aggregate price if it is trading, was published no more
+    /// than `config.max_slot_age` slots before `current_slot` and its
+    /// confidence interval is within `config.max_confidence_pct` of the price.
+    pub fn get_validated_price(
+        &self,
+        current_slot: u64,
+        config: &PriceValidationConfig,
+    ) -> Result<PriceInfo> {
+        let agg = self.agg;
+        let status = agg.get_status()?;
+        if status != PriceStatus::Trading {
+            msg!("Oracle status = {:?}", status);
+            return wrap_error!(Err(ErrorCode::OracleNotTrading.into()));
+        }
+
+        let slot_age = current_slot.saturating_sub(self.valid_slot);
+        if slot_age > config.max_slot_age {
+            msg!(
+                "Oracle valid slot = {}, current slot = {}",
+                self.valid_slot,
+                current_slot
+            );
+            return wrap_error!(Err(ErrorCode::StaleOraclePrice.into()));
+        }
+
+        if agg.price <= 0 {
+            return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into()));
+        }
+        let max_confidence = (agg.price as u128)
+            .checked_mul(config.max_confidence_pct.into())
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(NATIVE_PRECISION_DENOMINATOR)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if agg.conf as u128 > max_confidence {
+            msg!("Oracle price = {}, confidence = {}", agg.price, agg.conf);
+            return wrap_error!(Err(ErrorCode::OracleConfidenceTooWide.into()));
+        }
+
+        Ok(agg)
+    }
+
+    /// Scales a value at the feed's exponent to the given decimal precision.
+    pub fn scale_to_precision(&self, value: u64, precision: u32) -> Result<u64> {
+        let expo: u32 = match self.expo.checked_neg().map(|expo| expo.try_into()) {
+            Some(Ok(expo)) => expo,
+            _ => return wrap_error!(Err(ErrorCode::InvalidOracleAccount.into())),
+        };
+        let precision_scale = 10u128
+            .checked_pow(precision)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let expo_scale = 10u128.checked_pow(expo).ok_or(ErrorCode::MathOverflow)?;
+        let scaled = (value as u128)
+            .checked_mul(precision_scale)
+            .and_then(|v| v.checked_div(expo_scale))
+            .and_then(|v| v.try_into().ok());
+        match scaled {
+            Some(scaled) => Ok(scaled),
+            None => wrap_error!(Err(ErrorCode::InvalidOracleAccount.into())),
+        }
+    }
+}
+
+/// Bounds applied by `Price::get_validated_price`.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Copy, Clone)]
+pub struct PriceValidationConfig {
+    // Max number of slots between `valid_slot` and the current slot.
+    pub max_slot_age: u64,
+    // Max confidence interval as a fraction of price (NATIVE_PRECISION_DENOMINATOR).
+    pub max_confidence_pct: u64,
+}
+
+#[cfg(target_endian = "little")]
+unsafe impl Zeroable for Price {}
+
+#[cfg(target_endian = "little")]
+unsafe impl Pod for Price {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOT: u64 = 1_000;
+    const PRICE: i64 = 100_000_000;
+    const CONFIG: PriceValidationConfig = PriceValidationConfig {
+        max_slot_age: 25,
+        max_confidence_pct: 2_000_000, // 2%
+    };
+
+    fn price() -> Price {
+        Price {
+            magic: MAGIC,
+            ver: VERSION,
+            atype: ACCOUNT_TYPE_PRICE,
+            expo: -6,
+            valid_slot: SLOT,
+            agg: PriceInfo {
+                price: PRICE,
+                conf: 0,
+                status: PriceStatus::Trading as u32,
+                ..PriceInfo::default()
+            },
+            ..Price::default()
+        }
+    }
+
+    // Loads `price` through an account owned by `owner`. Backed by u64s so
+    // the data is aligned like on-chain account data.
+    fn load_checked_with(price: &Price, owner: &Pubkey) -> bool {
+        let size = std::mem::size_of::<Price>();
+        let mut words = vec![0u64; (size + 7) / 8];
+        let data: &mut [u8] = bytemuck::cast_slice_mut(&mut words);
+        data[..size].copy_from_slice(bytemuck::bytes_of(price));
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, data, owner, false, 0);
+        let loaded = Price::load_checked(&account).is_ok();
+        loaded
+    }
+
+    #[test]
+    fn loads_valid_price_account() {
+        assert!(load_checked_with(&price(), &pyth_program::ID));
+    }
+
+    #[test]
+    fn rejects_wrong_owner() {
+        assert!(!load_checked_with(&price(), &Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut price = price();
+        price.magic = 0;
+        assert!(!load_checked_with(&price, &pyth_program::ID));
+    }
+
+    #[test]
+    fn rejects_bad_version() {
+        let mut price = price();
+        price.ver = VERSION + 1;
+        assert!(!load_checked_with(&price, &pyth_program::ID));
+    }
+
+    #[test]
+    fn rejects_wrong_account_type() {
+        let mut price = price();
+        price.atype = ACCOUNT_TYPE_PRICE - 1;
+        assert!(!load_checked_with(&price, &pyth_program::ID));
+    }
+
+    #[test]
+    fn rejects_stale_price() {
+        let price = price();
+        assert!(price
+            .get_validated_price(SLOT + CONFIG.max_slot_age, &CONFIG)
+            .is_ok());
+        assert!(price
+            .get_validated_price(SLOT + CONFIG.max_slot_age + 1, &CONFIG)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_wide_confidence() {
+        let mut price = price();
+        price.agg.conf = 2_000_000; // 2% of PRICE
+        assert!(price.get_validated_price(SLOT, &CONFIG).is_ok());
+        price.agg.conf += 1;
+        assert!(price.get_validated_price(SLOT, &CONFIG).is_err());
+    }
+
+    #[test]
+    fn rejects_status_other_than_trading() {
+        let mut price = price();
+        price.agg.status = PriceStatus::Halted as u32;
+        assert!(price.get_validated_price(SLOT, &CONFIG).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_status_value() {
+        let mut price = price();
+        price.agg.status = 4;
+        assert!(load_checked_with(&price, &pyth_program::ID));
+        assert!(price.get_validated_price(SLOT, &CONFIG).is_err());
+    }
+}
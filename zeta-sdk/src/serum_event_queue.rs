@@ -0,0 +1,152 @@
+// Reads fills out of a Serum event queue, e.g. `MarketAccounts.event_queue`.
+use crate::*;
+use std::convert::TryInto;
+
+// "serum" prefix and 7 byte "padding" suffix around every Serum account.
+const ACCOUNT_HEAD_PADDING: usize = 5;
+const ACCOUNT_TAIL_PADDING: usize = 7;
+const HEADER_SIZE: usize = 32; // account_flags, head, count, seq_num
+const EVENT_SIZE: usize = 88;
+
+const EVENT_FLAG_FILL: u8 = 0x1;
+const EVENT_FLAG_BID: u8 = 0x4;
+const EVENT_FLAG_MAKER: u8 = 0x8;
+
+/// A fill event from the queue, quantities are native Serum amounts.
+#[derive(Clone, Copy)]
+pub struct FillEvent {
+    pub side: Side,
+    pub maker: bool,
+    pub owner_slot: u8,
+    pub native_qty_released: u64,
+    pub native_qty_paid: u64,
+    pub native_fee_or_rebate: u64,
+    pub order_id: u128,
+    pub owner: Pubkey, // Open orders account
+    pub client_order_id: u64,
+}
+
+impl FillEvent {
+    /// Base (option) quantity traded.
+    pub fn get_base_quantity(&self) -> u64 {
+        match self.side {
+            Side::Bid => self.native_qty_released,
+            _ => self.native_qty_paid,
+        }
+    }
+
+    /// Quote (USDC) quantity traded, before fees.
+    pub fn get_quote_quantity(&self) -> u64 {
+        match self.side {
+            Side::Bid => self.native_qty_paid,
+            _ => self.native_qty_released,
+        }
+    }
+}
+
+/// Returns the unprocessed fills in the queue belonging to `open_orders`,
+/// oldest first.
+pub fn get_fills(event_queue: &AccountInfo, open_orders: &Pubkey) -> Result<Vec<FillEvent>> {
+    let data = event_queue.try_borrow_data()?;
+    parse_fills(&data[..], open_orders)
+}
+
+/// Same as `get_fills` but on raw account data, so it can be used off-chain.
+pub fn parse_fills(data: &[u8], open_orders: &Pubkey) -> Result<Vec<FillEvent>> {
+    if data.len() < ACCOUNT_HEAD_PADDING + HEADER_SIZE + ACCOUNT_TAIL_PADDING {
+        return wrap_error!(Err(ErrorCode::InvalidEventQueue.into()));
+    }
+    let data = &data[ACCOUNT_HEAD_PADDING..data.len() - ACCOUNT_TAIL_PADDING];
+    let (header, events) = data.split_at(HEADER_SIZE);
+
+    let capacity = events.len() / EVENT_SIZE;
+    let head = read_u64(header, 8) as usize;
+    let count = read_u64(header, 16) as usize;
+    if capacity == 0 || head >= capacity || count > capacity {
+        return wrap_error!(Err(ErrorCode::InvalidEventQueue.into()));
+    }
+
+    let mut fills = Vec::new();
+    for i in 0..count {
+        let offset = ((head + i) % capacity) * EVENT_SIZE;
+        let event = &events[offset..offset + EVENT_SIZE];
+        let flags = event[0];
+        if flags & EVENT_FLAG_FILL == 0 {
+            continue;
+        }
+        let owner = Pubkey::new(&event[48..80]);
+        if &owner != open_orders {
+            continue;
+        }
+        fills.push(FillEvent {
+            side: if flags & EVENT_FLAG_BID != 0 {
+                Side::Bid
+            } else {
+                Side::Ask
+            },
+            maker: flags & EVENT_FLAG_MAKER != 0,
+            owner_slot: event[1],
+            native_qty_released: read_u64(event, 8),
+            native_qty_paid: read_u64(event, 16),
+            native_fee_or_rebate: read_u64(event, 24),
+            order_id: u128::from_le_bytes(event[32..48].try_into().unwrap()),
+            owner,
+            client_order_id: read_u64(event, 80),
+        });
+    }
+    Ok(fills)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAPACITY: usize = 4;
+    const QUEUE_SIZE: usize =
+        ACCOUNT_HEAD_PADDING + HEADER_SIZE + CAPACITY * EVENT_SIZE + ACCOUNT_TAIL_PADDING;
+
+    fn write_event(data: &mut [u8], index: usize, flags: u8, owner: &Pubkey, qty: u64) {
+        let offset = ACCOUNT_HEAD_PADDING + HEADER_SIZE + index * EVENT_SIZE;
+        let event = &mut data[offset..offset + EVENT_SIZE];
+        event[0] = flags;
+        event[8..16].copy_from_slice(&qty.to_le_bytes());
+        event[48..80].copy_from_slice(owner.as_ref());
+        event[80..88].copy_from_slice(&(index as u64).to_le_bytes());
+    }
+
+    // Ring buffer of 4 events with head 2 and count 3, i.e. events 2, 3, 0.
+    fn setup(owner: &Pubkey, other: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; QUEUE_SIZE];
+        data[ACCOUNT_HEAD_PADDING + 8..ACCOUNT_HEAD_PADDING + 16]
+            .copy_from_slice(&2u64.to_le_bytes());
+        data[ACCOUNT_HEAD_PADDING + 16..ACCOUNT_HEAD_PADDING + 24]
+            .copy_from_slice(&3u64.to_le_bytes());
+        write_event(&mut data, 2, EVENT_FLAG_FILL | EVENT_FLAG_BID, owner, 10);
+        write_event(&mut data, 3, EVENT_FLAG_FILL, other, 20);
+        write_event(&mut data, 0, EVENT_FLAG_FILL | EVENT_FLAG_MAKER, owner, 30);
+        // Outside of [head, head + count), must be ignored.
+        write_event(&mut data, 1, EVENT_FLAG_FILL, owner, 40);
+        data
+    }
+
+    #[test]
+    fn parses_fills_for_owner_in_queue_order() {
+        let owner = Pubkey::new_unique();
+        let data = setup(&owner, &Pubkey::new_unique());
+        let fills = parse_fills(&data, &owner).unwrap();
+        assert_eq!(fills.len(), 2);
+        assert!(fills[0].side == Side::Bid && !fills[0].maker);
+        assert_eq!(fills[0].native_qty_released, 10);
+        assert!(fills[1].side == Side::Ask && fills[1].maker);
+        assert_eq!(fills[1].client_order_id, 0);
+    }
+
+    #[test]
+    fn rejects_truncated_queue() {
+        assert!(parse_fills(&[0u8; 16], &Pubkey::new_unique()).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// Reads order parameters out of a Serum market account, e.g.
+// `MarketAccounts.market`.
+use crate::*;
+use std::convert::TryInto;
+
+// "serum" prefix and 7 byte "padding" suffix around every Serum account.
+const ACCOUNT_HEAD_PADDING: usize = 5;
+const ACCOUNT_TAIL_PADDING: usize = 7;
+// Offsets into `MarketState`, shared by the permissioned `MarketStateV2`
+// Zeta markets use, which only appends fields.
+const COIN_LOT_SIZE_OFFSET: usize = 344;
+const PC_LOT_SIZE_OFFSET: usize = 352;
+const MARKET_STATE_SIZE: usize = 376;
+
+/// Lot sizes Serum converts an order's price and size with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarketParameters {
+    // Order sizes must be a multiple of this, POSITION_PRECISION.
+    pub coin_lot_size: u64,
+    // Tick size, order prices must be a multiple of this, PLATFORM_PRECISION.
+    pub pc_lot_size: u64,
+}
+
+/// Returns the lot sizes of `market`.
+pub fn get_market_parameters(market: &AccountInfo) -> Result<MarketParameters> {
+    let data = market.try_borrow_data()?;
+    parse_market_parameters(&data[..])
+}
+
+/// Same as `get_market_parameters` but on raw account data, so it can be used off-chain.
+pub fn parse_market_parameters(data: &[u8]) -> Result<MarketParameters> {
+    if data.len() < ACCOUNT_HEAD_PADDING + MARKET_STATE_SIZE + ACCOUNT_TAIL_PADDING {
+        return wrap_error!(Err(ErrorCode::InvalidSerumMarket.into()));
+    }
+    let data = &data[ACCOUNT_HEAD_PADDING..];
+    let parameters = MarketParameters {
+        coin_lot_size: read_u64(data, COIN_LOT_SIZE_OFFSET),
+        pc_lot_size: read_u64(data, PC_LOT_SIZE_OFFSET),
+    };
+    if parameters.coin_lot_size == 0 || parameters.pc_lot_size == 0 {
+        return wrap_error!(Err(ErrorCode::InvalidSerumMarket.into()));
+    }
+    Ok(parameters)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT_SIZE: usize = ACCOUNT_HEAD_PADDING + MARKET_STATE_SIZE + ACCOUNT_TAIL_PADDING;
+
+    fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+        let offset = ACCOUNT_HEAD_PADDING + offset;
+        data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn parses_lot_sizes() {
+        let mut data = vec![0u8; ACCOUNT_SIZE];
+        write_u64(&mut data, COIN_LOT_SIZE_OFFSET, 1);
+        write_u64(&mut data, PC_LOT_SIZE_OFFSET, 100);
+
+        assert_eq!(
+            parse_market_parameters(&data).unwrap(),
+            MarketParameters {
+                coin_lot_size: 1,
+                pc_lot_size: 100,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_short_or_uninitialized_market() {
+        assert!(parse_market_parameters(&[0u8; 16]).is_err());
+        assert!(parse_market_parameters(&[0u8; ACCOUNT_SIZE]).is_err());
+    }
+}
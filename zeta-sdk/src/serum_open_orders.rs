@@ -0,0 +1,97 @@
+// Reads resting orders out of a Serum open orders account, e.g.
+// `CancelAccounts.open_orders`.
+use crate::*;
+use std::convert::TryInto;
+
+// "serum" prefix and 7 byte "padding" suffix around every Serum account.
+const ACCOUNT_HEAD_PADDING: usize = 5;
+const ACCOUNT_TAIL_PADDING: usize = 7;
+// account_flags, market, owner, native coin/pc free/total.
+const FREE_SLOT_BITS_OFFSET: usize = 104;
+const IS_BID_BITS_OFFSET: usize = 120;
+const ORDERS_OFFSET: usize = 136;
+const MAX_ORDERS: usize = 128;
+const OPEN_ORDERS_SIZE: usize = ORDERS_OFFSET + MAX_ORDERS * (16 + 8) + 8;
+
+/// A resting order in an open orders slot.
+#[derive(Clone, Copy)]
+pub struct OpenOrder {
+    pub side: Side,
+    pub order_id: u128,
+    pub client_order_id: u64,
+}
+
+/// Returns the resting orders of `open_orders` in slot order.
+pub fn get_open_orders(open_orders: &AccountInfo) -> Result<Vec<OpenOrder>> {
+    let data = open_orders.try_borrow_data()?;
+    parse_open_orders(&data[..])
+}
+
+/// Same as `get_open_orders` but on raw account data, so it can be used off-chain.
+pub fn parse_open_orders(data: &[u8]) -> Result<Vec<OpenOrder>> {
+    if data.len() != ACCOUNT_HEAD_PADDING + OPEN_ORDERS_SIZE + ACCOUNT_TAIL_PADDING {
+        return wrap_error!(Err(ErrorCode::InvalidOpenOrders.into()));
+    }
+    let data = &data[ACCOUNT_HEAD_PADDING..data.len() - ACCOUNT_TAIL_PADDING];
+    let free_slot_bits = read_u128(data, FREE_SLOT_BITS_OFFSET);
+    let is_bid_bits = read_u128(data, IS_BID_BITS_OFFSET);
+    let client_order_ids_offset = ORDERS_OFFSET + MAX_ORDERS * 16;
+
+    let mut orders = Vec::new();
+    for slot in 0..MAX_ORDERS {
+        let bit = 1u128 << slot;
+        if free_slot_bits & bit != 0 {
+            continue;
+        }
+        orders.push(OpenOrder {
+            side: if is_bid_bits & bit != 0 {
+                Side::Bid
+            } else {
+                Side::Ask
+            },
+            order_id: read_u128(data, ORDERS_OFFSET + slot * 16),
+            client_order_id: u64::from_le_bytes(
+                data[client_order_ids_offset + slot * 8..client_order_ids_offset + slot * 8 + 8]
+                    .try_into()
+                    .unwrap(),
+            ),
+        });
+    }
+    Ok(orders)
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT_SIZE: usize = ACCOUNT_HEAD_PADDING + OPEN_ORDERS_SIZE + ACCOUNT_TAIL_PADDING;
+
+    fn write_u128(data: &mut [u8], offset: usize, value: u128) {
+        let offset = ACCOUNT_HEAD_PADDING + offset;
+        data[offset..offset + 16].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn parses_used_slots_only() {
+        let mut data = vec![0u8; ACCOUNT_SIZE];
+        // Slots 0 and 5 used, slot 5 is a bid.
+        write_u128(&mut data, FREE_SLOT_BITS_OFFSET, !(1 | 1 << 5));
+        write_u128(&mut data, IS_BID_BITS_OFFSET, 1 << 5);
+        write_u128(&mut data, ORDERS_OFFSET, 10);
+        write_u128(&mut data, ORDERS_OFFSET + 5 * 16, 50);
+
+        let orders = parse_open_orders(&data).unwrap();
+        assert_eq!(orders.len(), 2);
+        assert!(orders[0].side == Side::Ask && orders[0].order_id == 10);
+        assert!(orders[1].side == Side::Bid && orders[1].order_id == 50);
+    }
+
+    #[test]
+    fn rejects_wrong_size() {
+        assert!(parse_open_orders(&[0u8; 16]).is_err());
+    }
+}
@@ -0,0 +1,1222 @@
+use crate::*;
+use bytemuck::{Pod, Zeroable};
+use std::convert::{From, TryFrom, TryInto};
+
+#[zero_copy]
+#[derive(Default)]
+pub struct ProductGreeks {
+    pub delta: u64,
+    pub vega: AnchorDecimal,
+    pub volatility: AnchorDecimal,
+} // 40
+
+#[zero_copy]
+#[derive(Default)]
+pub struct AnchorDecimal {
+    pub flags: u32,
+    pub hi: u32,
+    pub lo: u32,
+    pub mid: u32,
+}
+
+impl From<Decimal> for AnchorDecimal {
+    fn from(decimal: Decimal) -> AnchorDecimal {
+        AnchorDecimal {
+            flags: decimal.flags,
+            hi: decimal.hi,
+            lo: decimal.lo,
+            mid: decimal.mid,
+        }
+    }
+}
+
+impl From<AnchorDecimal> for Decimal {
+    fn from(decimal: AnchorDecimal) -> Decimal {
+        Decimal {
+            flags: decimal.flags,
+            hi: decimal.hi,
+            lo: decimal.lo,
+            mid: decimal.mid,
+        }
+    }
+}
+
+#[cfg(target_endian = "little")]
+unsafe impl Zeroable for AnchorDecimal {}
+
+#[cfg(target_endian = "little")]
+unsafe impl Pod for AnchorDecimal {}
+
+#[account(zero_copy)]
+pub struct Greeks {
+    pub nonce: u8,
+    pub mark_prices: [u64; 46],
+    pub _mark_prices_padding: [u64; 92],
+
+    pub product_greeks: [ProductGreeks; 22], // TOTAL_MARKETS
+    pub _product_greeks_padding: [ProductGreeks; 44],
+
+    pub update_timestamp: [u64; 2],          // per expiration.
+    pub _update_timestamp_padding: [u64; 4], // per expiration.
+
+    pub retreat_expiration_timestamp: [u64; 2], // per expiration.
+    pub _retreat_expiration_timestamp_padding: [u64; 4], // per expiration.
+
+    pub interest_rate: [i64; 2],
+    pub _interest_rate_padding: [i64; 4],
+
+    pub nodes: [u64; 5],                // 5 per expiration // f/k space nodes
+    pub volatility: [u64; 10],          // 5 per expiration // volatility nodes
+    pub _volatility_padding: [u64; 20], // 5 per expiration // volatility nodes
+
+    pub node_keys: [Pubkey; 138], // 4416
+    pub _padding: [u8; 1647],     // 10240 - 8585 - 8
+} // 1 + 1104 + 2640 + 48 + 48 + 48 + 280 + 4416 = 8585
+
+impl Greeks {
+    pub fn get_mark_prices_slice(&self, expiry_index: usize) -> &[u64] {
+        let head = expiry_index * NUM_PRODUCTS_PER_SERIES;
+        &self.mark_prices[head..head + NUM_PRODUCTS_PER_SERIES]
+    }
+
+    pub fn get_product_greeks_slice(&self, expiry_index: usize) -> &[ProductGreeks] {
+        let head = expiry_index * NUM_STRIKES;
+        &self.product_greeks[head..head + NUM_STRIKES]
+    }
+
+    pub fn get_volatility_slice(&self, expiry_index: usize) -> &[u64] {
+        let head = expiry_index * VOLATILITY_POINTS;
+        &self.volatility[head..head + VOLATILITY_POINTS]
+    }
+
+    pub fn get_futures_price(&self, expiry_index: usize) -> u64 {
+        self.mark_prices[expiry_index * NUM_PRODUCTS_PER_SERIES + NUM_PRODUCTS_PER_SERIES - 1]
+    }
+
+    // Index into `product_greeks` for an option market index. Calls and puts
+    // of the same strike share their product greeks.
+    fn get_product_greeks_index(market_index: usize) -> usize {
+        let expiry_index = market_index / NUM_PRODUCTS_PER_SERIES;
+        let product_index = market_index % NUM_PRODUCTS_PER_SERIES;
+        expiry_index * NUM_STRIKES + product_index % NUM_STRIKES
+    }
+
+    /// Signed delta (PRICING_PRECISION) of one lot of the product at `market_index`.
+    /// Puts are call delta - 1 and futures are 1.
+    pub fn get_delta(&self, market_index: usize, kind: Kind) -> Result<i128> {
+        let call_delta = self.product_greeks[Self::get_product_greeks_index(market_index)].delta;
+        match kind {
+            Kind::Call => Ok(call_delta as i128),
+            Kind::Put => (call_delta as i128)
+                .checked_sub(10i128.pow(PRICING_PRECISION))
+                .ok_or(ErrorCode::MathOverflow.into()),
+            Kind::Future => Ok(10i128.pow(PRICING_PRECISION)),
+            Kind::Uninitialized => wrap_error!(Err(ErrorCode::UnsupportedKind.into())),
+        }
+    }
+
+    /// Vega of one lot of the product at `market_index`, zero for futures.
+    pub fn get_vega(&self, market_index: usize, kind: Kind) -> Result<Decimal> {
+        match kind {
+            Kind::Call | Kind::Put => Ok(Decimal::from(
+                self.product_greeks[Self::get_product_greeks_index(market_index)].vega,
+            )),
+            Kind::Future => Ok(Decimal::zero()),
+            Kind::Uninitialized => wrap_error!(Err(ErrorCode::UnsupportedKind.into())),
+        }
+    }
+
+    /// Errors if the greeks of `expiry_index` were last updated more than
+    /// `max_age` seconds before `current_ts`, or if a volatility / interest
+    /// retreat has expired since the last update, i.e. the stored mark prices
+    /// still include it.
+    pub fn check_freshness(
+        &self,
+        expiry_index: usize,
+        current_ts: u64,
+        max_age: u64,
+    ) -> Result<()> {
+        let update_ts = self.update_timestamp[expiry_index];
+        if current_ts.saturating_sub(update_ts) > max_age {
+            msg!(
+                "Greeks for expiry {} updated at {}, current ts = {}",
+                expiry_index,
+                update_ts,
+                current_ts
+            );
+            return wrap_error!(Err(ErrorCode::StaleGreeks.into()));
+        }
+
+        let retreat_expiration_ts = self.retreat_expiration_timestamp[expiry_index];
+        if retreat_expiration_ts != 0
+            && retreat_expiration_ts <= current_ts
+            && update_ts < retreat_expiration_ts
+        {
+            msg!(
+                "Greeks for expiry {} updated at {}, retreat expired at {}",
+                expiry_index,
+                update_ts,
+                retreat_expiration_ts
+            );
+            return wrap_error!(Err(ErrorCode::StaleGreeks.into()));
+        }
+        Ok(())
+    }
+
+    /// `check_freshness` for every live series, for values that depend on
+    /// mark prices across expiries, e.g. margin requirements and NAV.
+    pub fn check_live_freshness(
+        &self,
+        zeta_group: &ZetaGroup,
+        current_ts: u64,
+        max_age: u64,
+    ) -> Result<()> {
+        for (expiry_index, series) in zeta_group.expiry_series.iter().enumerate() {
+            if series.get_status(current_ts) == ExpirySeriesStatus::Live {
+                self.check_freshness(expiry_index, current_ts, max_age)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[account(zero_copy)]
+pub struct ZetaGroup {
+    pub nonce: u8,
+    pub vault_nonce: u8,
+    pub insurance_vault_nonce: u8,
+    pub front_expiry_index: u8,
+    pub halt_state: HaltState,
+    pub underlying_mint: Pubkey,
+    pub oracle: Pubkey,
+    pub greeks: Pubkey,
+    pub pricing_parameters: PricingParameters, // 64
+    pub margin_parameters: MarginParameters,   // 80
+
+    pub products: [Product; 46], // 138 * 43 = 5934
+    pub products_padding: [Product; 92],
+    pub expiry_series: [ExpirySeries; 2], // 32 * 6 = 192
+    pub expiry_series_padding: [ExpirySeries; 4],
+
+    pub total_insurance_vault_deposits: u64,
+    pub padding: [u8; 1063], // 7680 - 6617
+} // 1 + 1 + 1 + 1 + 167 + 32 + 32 + 32 + 96 + 120 + 5934 + 192 + 8 = 6617
+
+#[zero_copy]
+pub struct HaltState {
+    halted: bool,
+    spot_price: u64, // Set with precision 6.
+    timestamp: u64,
+    mark_prices_set: [bool; 2],
+    _mark_prices_set_padding: [bool; 4],
+    market_nodes_cleaned: [bool; 2],
+    _market_nodes_cleaned_padding: [bool; 4],
+    market_cleaned: [bool; 46],
+    _market_cleaned_padding: [bool; 92],
+} // 1 + 8 + 8 + 6 + 6 + 46 + 92 = 167
+
+impl HaltState {
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    // Spot price the group was halted at, HALT_SPOT_PRICE_PRECISION.
+    pub fn get_spot_price(&self) -> u64 {
+        self.spot_price
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    // Whether halt mark prices have been set for the expiry.
+    pub fn is_mark_prices_set(&self, expiry_index: usize) -> bool {
+        self.mark_prices_set[expiry_index]
+    }
+
+    pub fn is_market_nodes_cleaned(&self, expiry_index: usize) -> bool {
+        self.market_nodes_cleaned[expiry_index]
+    }
+
+    pub fn is_market_cleaned(&self, market_index: usize) -> bool {
+        self.market_cleaned[market_index]
+    }
+}
+
+#[zero_copy]
+#[derive(Default)]
+pub struct PricingParameters {
+    pub option_trade_normalizer: AnchorDecimal,
+    pub future_trade_normalizer: AnchorDecimal,
+    pub max_volatility_retreat: AnchorDecimal,
+    pub max_interest_retreat: AnchorDecimal,
+    pub max_delta: u64,
+    pub min_delta: u64,
+    pub padding: [u8; 32],
+} // 64
+
+#[zero_copy]
+#[derive(Default)]
+pub struct MarginParameters {
+    // Futures
+    pub future_margin_initial: u64,
+    pub future_margin_maintenance: u64,
+
+    // Options initial
+    pub option_mark_percentage_long_initial: u64,
+    pub option_spot_percentage_long_initial: u64,
+    pub option_spot_percentage_short_initial: u64,
+    pub option_dynamic_percentage_short_initial: u64,
+
+    // Options maintenance
+    pub option_mark_percentage_long_maintenance: u64,
+    pub option_spot_percentage_long_maintenance: u64,
+    pub option_spot_percentage_short_maintenance: u64,
+    pub option_dynamic_percentage_short_maintenance: u64,
+
+    // Other parameters
+    pub option_short_put_cap_percentage: u64,
+    pub padding: [u8; 32],
+} // 120 bytes.
+
+impl ZetaGroup {
+    pub fn is_halted(&self) -> bool {
+        self.halt_state.is_halted()
+    }
+
+    pub fn get_strike(&self, index: usize) -> Result<u64> {
+        self.products[index].strike.get_strike()
+    }
+
+    pub fn get_products_slice(&self, expiry_index: usize) -> &[Product] {
+        let head = expiry_index * NUM_PRODUCTS_PER_SERIES;
+        &self.products[head..head + NUM_PRODUCTS_PER_SERIES]
+    }
+
+    pub fn get_product_and_expiry_index_by_key(&self, market: &Pubkey) -> Result<(usize, usize)> {
+        let index = self
+            .products
+            .binary_search_by_key(&market, |product| &product.market);
+
+        match index {
+            Err(_) => wrap_error!(Err(ErrorCode::InvalidProductMarketKey.into())),
+            Ok(i) => Ok((i, self.get_expiry_index_by_product_index(i))),
+        }
+    }
+
+    pub fn get_product_index_by_key(&self, market: &Pubkey) -> Result<usize> {
+        let index = self
+            .products
+            .binary_search_by_key(&market, |product| &product.market);
+
+        match index {
+            Err(_) => wrap_error!(Err(ErrorCode::InvalidProductMarketKey.into())),
+            Ok(i) => Ok(i),
+        }
+    }
+
+    pub fn get_expiry_series_by_key(&self, market: &Pubkey) -> Result<&ExpirySeries> {
+        let index = self
+            .products
+            .binary_search_by_key(&market, |product| &product.market);
+
+        match index {
+            Err(_) => wrap_error!(Err(ErrorCode::InvalidProductMarketKey.into())),
+            Ok(i) => Ok(self.get_expiry_series_by_product_index(i)),
+        }
+    }
+
+    pub fn get_expiry_series_by_product_index(&self, index: usize) -> &ExpirySeries {
+        &self.expiry_series[self.get_expiry_index_by_product_index(index)]
+    }
+
+    pub fn get_expiry_index_by_product_index(&self, index: usize) -> usize {
+        assert!(index < self.products.len());
+        let expiry_index = index.checked_div(NUM_PRODUCTS_PER_SERIES).unwrap();
+        assert!(expiry_index < self.expiry_series.len());
+        expiry_index
+    }
+
+    /// This function should validate an expiry index is:
+    /// 1. Live at `current_timestamp`, in a zeta group that isn't halted
+    /// 2. Strike is set
+    /// 3. Pricing update was within the required intervals.
+    pub fn validate_series_tradeable(
+        &self,
+        expiry_index: usize,
+        current_timestamp: u64,
+    ) -> Result<()> {
+        if self.is_halted() {
+            msg!("Zeta group is halted");
+            return wrap_error!(Err(ErrorCode::MarketNotLive.into()));
+        }
+        let series_status = self.expiry_series[expiry_index].get_status(current_timestamp);
+        if series_status != ExpirySeriesStatus::Live {
+            msg!("Series status = {:?}", series_status);
+            return wrap_error!(Err(ErrorCode::MarketNotLive.into()));
+        }
+
+        let products = self.get_products_slice(expiry_index);
+        // We don't need to check product.dirty as status implies that.
+        // We only need to check a singular product for strike set in the series.
+        if !products[0].strike.is_set() {
+            return wrap_error!(Err(ErrorCode::ProductStrikeUninitialized.into()));
+        }
+        Ok(())
+    }
+
+    pub fn get_back_expiry_index(&self) -> usize {
+        // This is built in with the invariant that on series expiration,
+        // the series index of the expired set is changed to be the next
+        // latest timestamp.
+        // This condition may not hold if we change the series layout.
+        match self.front_expiry_index {
+            0 => (self.expiry_series.len() - 1),
+            _ => (self.front_expiry_index - 1).into(),
+        }
+    }
+
+    // Return the expiry timestamp that is furthest in the future.
+    pub fn get_back_expiry_ts(&self) -> u64 {
+        self.expiry_series[self.get_back_expiry_index()].expiry_ts
+    }
+
+    // Does a wrapped -1 to the passed in expiry_index
+    pub fn get_previous_expiry_index(&self, expiry_index: usize) -> usize {
+        match expiry_index {
+            0 => (self.expiry_series.len() - 1),
+            _ => (expiry_index - 1).into(),
+        }
+    }
+}
+
+#[zero_copy]
+pub struct ExpirySeries {
+    pub active_ts: u64,
+    pub expiry_ts: u64,
+    pub dirty: bool,
+    pub padding: [u8; 15], // 32 - 17
+}
+
+impl ExpirySeries {
+    pub fn status(&self) -> Result<ExpirySeriesStatus> {
+        if self.active_ts == u64::default() || self.expiry_ts == u64::default() {
+            return Ok(ExpirySeriesStatus::Uninitialized);
+        };
+        let clock = Clock::get()?;
+        Ok(self.get_status(clock.unix_timestamp as u64))
+    }
+
+    // Same as `status` but evaluated at a caller supplied timestamp,
+    // so it can be used without the clock sysvar.
+    pub fn get_status(&self, current_ts: u64) -> ExpirySeriesStatus {
+        if self.active_ts == u64::default() || self.expiry_ts == u64::default() {
+            ExpirySeriesStatus::Uninitialized
+        } else if self.dirty {
+            ExpirySeriesStatus::ExpiredDirty
+        } else if current_ts < self.active_ts {
+            ExpirySeriesStatus::Initialized
+        } else if current_ts >= self.active_ts && current_ts < self.expiry_ts {
+            ExpirySeriesStatus::Live
+        } else {
+            ExpirySeriesStatus::Expired
+        }
+    }
+}
+
+// To mimic an Option<T> as anchor doesn't support zero_copy Option<T> deserialization yet.
+// Also, this implementation saves 7 bytes of space :)
+#[zero_copy]
+pub struct Strike {
+    pub is_set: bool,
+    pub value: u64,
+}
+
+impl Strike {
+    pub fn is_set(&self) -> bool {
+        self.is_set
+    }
+
+    pub fn get_strike(&self) -> Result<u64> {
+        if !self.is_set() {
+            return Err(ErrorCode::ProductStrikeUninitialized.into());
+        }
+        Ok(self.value)
+    }
+}
+
+#[zero_copy]
+pub struct Product {
+    // Serum market
+    pub market: Pubkey,
+    pub strike: Strike,
+    // Tracks whether the market has been wiped after expiration
+    pub dirty: bool,
+    pub kind: Kind,
+} // 32 + 9 + 1 + 1 = 43 bytes
+
+impl Product {
+    // Strike to margin the product against. Futures have no strike and their
+    // margin doesn't depend on one, options with an unset strike aren't margined.
+    pub fn get_margin_strike(&self) -> Option<u64> {
+        match self.kind {
+            Kind::Future => Some(0),
+            _ => self.strike.get_strike().ok(),
+        }
+    }
+}
+
+#[zero_copy]
+#[derive(Default)]
+pub struct Position {
+    pub position: i64,
+    pub cost_of_trades: u64,
+    pub closing_orders: u64,
+    pub opening_orders: [u64; 2],
+} // 8 + 8 + 8 + 16 = 40
+
+impl Position {
+    pub fn check_open(&self, side: Side) -> bool {
+        (side == Side::Bid && self.position >= 0) || (side == Side::Ask && self.position <= 0)
+    }
+
+    pub fn has_active_orders(&self) -> bool {
+        self.opening_orders[0] != 0 || self.opening_orders[1] != 0 || self.closing_orders != 0
+    }
+
+    pub fn get_initial_margin(
+        &self,
+        mark_price: u64,
+        product: &Product,
+        spot: u64,
+        margin_parameters: &MarginParameters,
+    ) -> Result<u64> {
+        let initial_margin_requirement: u128 = match product.get_margin_strike() {
+            Some(strike) => {
+                let mut long_init_margin: u128 = 0;
+                let mut short_init_margin: u128 = 0;
+                if self.opening_orders[0] > 0 {
+                    long_init_margin = (self.opening_orders[0] as u128)
+                        .checked_mul(
+                            get_initial_margin_per_lot(
+                                spot,
+                                strike,
+                                mark_price,
+                                product.kind,
+                                Side::Bid,
+                                margin_parameters,
+                            )?
+                            .into(),
+                        )
+                        .ok_or(ErrorCode::MathOverflow)?;
+                }
+
+                if self.opening_orders[1] > 0 {
+                    short_init_margin = (self.opening_orders[1] as u128)
+                        .checked_mul(
+                            get_initial_margin_per_lot(
+                                spot,
+                                strike,
+                                mark_price,
+                                product.kind,
+                                Side::Ask,
+                                margin_parameters,
+                            )?
+                            .into(),
+                        )
+                        .ok_or(ErrorCode::MathOverflow)?;
+                }
+                long_init_margin
+                    .checked_add(short_init_margin)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(POSITION_PRECISION_DENOMINATOR)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+            None => 0,
+        };
+        initial_margin_requirement
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    pub fn get_maintenance_margin(
+        &self,
+        mark_price: u64,
+        product: &Product,
+        spot: u64,
+        margin_parameters: &MarginParameters,
+    ) -> Result<u64> {
+        let maintenance_margin_requirement: u128 = match product.get_margin_strike() {
+            Some(strike) => {
+                let mut margin: u128 = 0;
+                if self.position != 0 {
+                    margin = (self.position.unsigned_abs() as u128)
+                        .checked_mul(
+                            get_maintenance_margin_per_lot(
+                                spot,
+                                strike,
+                                mark_price,
+                                product.kind,
+                                self.position >= 0,
+                                margin_parameters,
+                            )?
+                            .into(),
+                        )
+                        .ok_or(ErrorCode::MathOverflow)?
+                        .checked_div(POSITION_PRECISION_DENOMINATOR)
+                        .ok_or(ErrorCode::MathOverflow)?
+                }
+                margin
+            }
+            None => 0,
+        };
+        maintenance_margin_requirement
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    pub fn get_unrealized_pnl(&self, mark_price: u64) -> Result<i64> {
+        if self.position == 0 {
+            return Ok(0);
+        }
+        let market_value = (self.position as i128)
+            .checked_mul(mark_price as i128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(POSITION_PRECISION_DENOMINATOR as i128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let pnl = if self.position > 0 {
+            market_value.checked_sub(self.cost_of_trades as i128)
+        } else {
+            market_value.checked_add(self.cost_of_trades as i128)
+        };
+        pnl.ok_or(ErrorCode::MathOverflow)?
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    /// Books a resting order. The part that reduces the current position
+    /// counts as closing, the rest as opening.
+    pub fn apply_order(&mut self, side: Side, size: u64) -> Result<()> {
+        let side_index = get_side_index(side)?;
+        let closing_size = if self.check_open(side) {
+            0
+        } else {
+            self.position
+                .unsigned_abs()
+                .saturating_sub(self.closing_orders)
+                .min(size)
+        };
+        self.closing_orders = self
+            .closing_orders
+            .checked_add(closing_size)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.opening_orders[side_index] = self.opening_orders[side_index]
+            .checked_add(size - closing_size)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Applies a fill of `size` lots at `price` (6.dp per lot) and returns the
+    /// realized pnl of the part that closed the existing position.
+    pub fn apply_fill(&mut self, side: Side, size: u64, price: u64) -> Result<i64> {
+        let signed_size = i64::try_from(size).map_err(|_| ErrorCode::MathOverflow)?;
+        let signed_size = match side {
+            Side::Bid => signed_size,
+            Side::Ask => -signed_size,
+            Side::Uninitialized => return wrap_error!(Err(ErrorCode::InvalidSide.into())),
+        };
+
+        let closing_size = if self.check_open(side) {
+            0
+        } else {
+            self.position.unsigned_abs().min(size)
+        };
+
+        let mut realized_pnl: i128 = 0;
+        if closing_size > 0 {
+            let closed_cost = (self.cost_of_trades as u128)
+                .checked_mul(closing_size as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(self.position.unsigned_abs() as u128)
+                .ok_or(ErrorCode::MathOverflow)?;
+            let closed_value = (closing_size as u128)
+                .checked_mul(price as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(POSITION_PRECISION_DENOMINATOR)
+                .ok_or(ErrorCode::MathOverflow)?;
+            realized_pnl = if self.position > 0 {
+                closed_value as i128 - closed_cost as i128
+            } else {
+                closed_cost as i128 - closed_value as i128
+            };
+            self.cost_of_trades = self
+                .cost_of_trades
+                .checked_sub(closed_cost as u64)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        let opening_cost = ((size - closing_size) as u128)
+            .checked_mul(price as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(POSITION_PRECISION_DENOMINATOR)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.cost_of_trades = (self.cost_of_trades as u128)
+            .checked_add(opening_cost)
+            .ok_or(ErrorCode::MathOverflow)?
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow)?;
+        self.position = self
+            .position
+            .checked_add(signed_size)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        realized_pnl
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow.into())
+    }
+}
+
+/// Hypothetical order or fill used to project a margin account's
+/// requirements before trading.
+#[derive(Clone, Copy)]
+pub struct SimulatedOrder {
+    pub market_index: usize,
+    pub side: Side,
+    pub size: u64,  // POSITION_PRECISION
+    pub price: u64, // 6.dp per lot
+    // A fill changes the position, otherwise the order rests on the book.
+    pub is_fill: bool,
+}
+
+/// Projected state of a margin account after a set of simulated orders.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MarginProjection {
+    pub balance: i64,
+    pub initial_margin: u64,
+    pub maintenance_margin: u64,
+    pub unrealized_pnl: i64,
+    // balance + unrealized_pnl - initial_margin - maintenance_margin
+    pub buffer: i64,
+}
+
+impl MarginProjection {
+    /// Whether the projected account would pass Zeta's margin check.
+    pub fn is_sufficient(&self) -> bool {
+        self.buffer > 0
+    }
+}
+
+#[account(zero_copy)]
+pub struct MarginAccount {
+    pub authority: Pubkey,
+    pub nonce: u8,
+    pub balance: u64,
+    pub force_cancel_flag: bool,
+
+    pub open_orders_nonce: [u8; 138],
+    pub series_expiry: [u64; 6], // Tracks the expiration of this index, set to 0 if clean
+    pub positions: [Position; 46], // 138 * 40 = 5520
+    pub positions_padding: [Position; 92], // For future when we add more expiries.
+
+    pub rebalance_amount: i64,
+    pub _padding: [u8; 388],
+} // 32 + 1 + 8 + 1 + 138 + 48 + 40 * 138 + 8 = 5756 + 388 = 6144
+
+impl MarginAccount {
+    pub fn get_positions_slice(&self, expiry_index: usize) -> &[Position] {
+        let head = expiry_index * NUM_PRODUCTS_PER_SERIES;
+        &self.positions[head..head + NUM_PRODUCTS_PER_SERIES]
+    }
+
+    pub fn get_futures_position(&self, expiry_index: usize) -> Position {
+        self.positions[expiry_index * NUM_PRODUCTS_PER_SERIES + SERIES_FUTURE_INDEX]
+    }
+
+    pub fn get_initial_margin(
+        &self,
+        greeks: &Greeks,
+        zeta_group: &ZetaGroup,
+        spot: u64,
+    ) -> Result<u64> {
+        let initial_margin_requirement =
+            get_initial_margin_for_positions(&self.positions, greeks, zeta_group, spot)?;
+
+        msg!(
+            "Total Initial margin requirement = {}",
+            initial_margin_requirement
+        );
+        Ok(initial_margin_requirement)
+    }
+
+    pub fn get_maintenance_margin(
+        &self,
+        greeks: &Greeks,
+        zeta_group: &ZetaGroup,
+        spot: u64,
+    ) -> Result<u64> {
+        let maintenance_margin_requirement =
+            get_maintenance_margin_for_positions(&self.positions, greeks, zeta_group, spot)?;
+
+        msg!(
+            "Total Maintenance requirement = {}",
+            maintenance_margin_requirement
+        );
+        Ok(maintenance_margin_requirement)
+    }
+
+    pub fn get_margin_requirement(
+        &self,
+        greeks: &Greeks,
+        zeta_group: &ZetaGroup,
+        spot: u64,
+    ) -> Result<u64> {
+        self.get_initial_margin(greeks, zeta_group, spot)?
+            .checked_add(self.get_maintenance_margin(greeks, zeta_group, spot)?)
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    pub fn get_unrealized_pnl(&self, greeks: &Greeks) -> Result<i64> {
+        get_unrealized_pnl_for_positions(&self.positions, greeks)
+    }
+
+    // Unrealized pnl of the futures position against the futures mark.
+    pub fn get_futures_unrealized_pnl(&self, greeks: &Greeks, expiry_index: usize) -> Result<i64> {
+        self.get_futures_position(expiry_index)
+            .get_unrealized_pnl(greeks.get_futures_price(expiry_index))
+    }
+
+    pub fn check_margin_requirement(
+        &self,
+        greeks: &Greeks,
+        zeta_group: &ZetaGroup,
+        native_spot: u64,
+    ) -> Result<bool> {
+        let margin_requirement = self.get_margin_requirement(&greeks, &zeta_group, native_spot)?;
+        Ok(self.get_margin_buffer(greeks, margin_requirement)? > 0)
+    }
+
+    /// Whether the account is below its maintenance margin and can be
+    /// liquidated. Usable off-chain to find accounts before submitting.
+    pub fn is_liquidatable(
+        &self,
+        greeks: &Greeks,
+        zeta_group: &ZetaGroup,
+        native_spot: u64,
+    ) -> Result<bool> {
+        let maintenance_margin = self.get_maintenance_margin(&greeks, &zeta_group, native_spot)?;
+        Ok(self.get_margin_buffer(greeks, maintenance_margin)? < 0)
+    }
+
+    // balance + unrealized pnl - margin_requirement
+    fn get_margin_buffer(&self, greeks: &Greeks, margin_requirement: u64) -> Result<i64> {
+        let pnl = self.get_unrealized_pnl(&greeks)?;
+        let margin_requirement =
+            i64::try_from(margin_requirement).map_err(|_| ErrorCode::MathOverflow)?;
+        let buffer = i64::try_from(self.balance)
+            .map_err(|_| ErrorCode::MathOverflow)?
+            .checked_add(pnl)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_sub(margin_requirement)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "MarginAccount: Pnl = {}, margin_requirement = {}, buffer = {}, balance = {}",
+            pnl,
+            margin_requirement,
+            buffer,
+            self.balance,
+        );
+
+        Ok(buffer)
+    }
+
+    pub fn has_positions(&self) -> bool {
+        self.positions.iter().any(|position| position.position != 0)
+    }
+
+    /// Expiry of the series this account holds positions in at
+    /// `expiry_index`. Zeta reuses expiry slots, so once a slot is
+    /// re-initialised this is the only record of the series to settle.
+    pub fn get_settlement_expiry(&self, expiry_index: usize, current_ts: u64) -> Result<u64> {
+        let expiry_ts = *self
+            .series_expiry
+            .get(expiry_index)
+            .ok_or(ErrorCode::InvalidExpiryIndex)?;
+        if expiry_ts == 0 || current_ts < expiry_ts {
+            msg!(
+                "Series expiry = {}, current timestamp = {}",
+                expiry_ts,
+                current_ts
+            );
+            return wrap_error!(Err(ErrorCode::SeriesNotExpired.into()));
+        }
+        Ok(expiry_ts)
+    }
+
+    pub fn has_active_orders(&self) -> bool {
+        let has_active_orders = self
+            .positions
+            .iter()
+            .find(|position| position.has_active_orders());
+        match has_active_orders {
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Projects margin requirements, unrealized pnl and buffer as if
+    /// `orders` were placed (or filled) on top of the current positions.
+    /// Nothing is written back to the account.
+    pub fn simulate_orders(
+        &self,
+        orders: &[SimulatedOrder],
+        greeks: &Greeks,
+        zeta_group: &ZetaGroup,
+        spot: u64,
+    ) -> Result<MarginProjection> {
+        let mut positions = self.positions;
+        let mut balance = i64::try_from(self.balance).map_err(|_| ErrorCode::MathOverflow)?;
+        for order in orders {
+            let position = match positions.get_mut(order.market_index) {
+                Some(position) => position,
+                None => return wrap_error!(Err(ErrorCode::InvalidMarketIndex.into())),
+            };
+            if order.is_fill {
+                balance = balance
+                    .checked_add(position.apply_fill(order.side, order.size, order.price)?)
+                    .ok_or(ErrorCode::MathOverflow)?;
+            } else {
+                position.apply_order(order.side, order.size)?;
+            }
+        }
+
+        let initial_margin =
+            get_initial_margin_for_positions(&positions, greeks, zeta_group, spot)?;
+        let maintenance_margin =
+            get_maintenance_margin_for_positions(&positions, greeks, zeta_group, spot)?;
+        let unrealized_pnl = get_unrealized_pnl_for_positions(&positions, greeks)?;
+        let margin_requirement = initial_margin
+            .checked_add(maintenance_margin)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let buffer = balance
+            .checked_add(unrealized_pnl)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_sub(i64::try_from(margin_requirement).map_err(|_| ErrorCode::MathOverflow)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(MarginProjection {
+            balance,
+            initial_margin,
+            maintenance_margin,
+            unrealized_pnl,
+            buffer,
+        })
+    }
+}
+
+fn get_initial_margin_for_positions(
+    positions: &[Position],
+    greeks: &Greeks,
+    zeta_group: &ZetaGroup,
+    spot: u64,
+) -> Result<u64> {
+    let mut initial_margin_requirement: u64 = 0;
+    for (i, position) in positions.iter().enumerate() {
+        initial_margin_requirement = initial_margin_requirement
+            .checked_add(position.get_initial_margin(
+                greeks.mark_prices[i],
+                &zeta_group.products[i],
+                spot,
+                &zeta_group.margin_parameters,
+            )?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    Ok(initial_margin_requirement)
+}
+
+fn get_maintenance_margin_for_positions(
+    positions: &[Position],
+    greeks: &Greeks,
+    zeta_group: &ZetaGroup,
+    spot: u64,
+) -> Result<u64> {
+    let mut maintenance_margin_requirement: u64 = 0;
+    for (i, position) in positions.iter().enumerate() {
+        maintenance_margin_requirement = maintenance_margin_requirement
+            .checked_add(position.get_maintenance_margin(
+                greeks.mark_prices[i],
+                &zeta_group.products[i],
+                spot,
+                &zeta_group.margin_parameters,
+            )?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    Ok(maintenance_margin_requirement)
+}
+
+fn get_unrealized_pnl_for_positions(positions: &[Position], greeks: &Greeks) -> Result<i64> {
+    let mut pnl: i64 = 0;
+    for (i, position) in positions.iter().enumerate() {
+        pnl = pnl
+            .checked_add(position.get_unrealized_pnl(greeks.mark_prices[i])?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    Ok(pnl)
+}
+
+// Enum Types
+#[repr(u8)]
+#[derive(PartialEq, Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum ExpirySeriesStatus {
+    Uninitialized = 0, // Still in default state
+    Initialized = 1,   // Initialized but not active yet
+    Live = 2,          // Active and trading.
+    Expired = 3,       // Intermediate state after active trading
+    ExpiredDirty = 4,  // State when series has expired but markets haven't been cleaned
+}
+
+#[repr(u8)]
+#[derive(PartialEq, Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum Kind {
+    Uninitialized = 0,
+    Call = 1,
+    Put = 2,
+    Future = 3,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, PartialEq, Eq, Clone, Copy)]
+pub enum Side {
+    Uninitialized = 0,
+    Bid = 1,
+    Ask = 2,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, PartialEq, Eq, Clone, Copy)]
+pub enum OrderType {
+    Limit = 0,
+    // Rejected instead of crossing the book.
+    PostOnly = 1,
+    // Fully filled immediately or not at all.
+    FillOrKill = 2,
+    // Fills what it can immediately, the remainder is cancelled.
+    ImmediateOrCancel = 3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    const NOW: u64 = 1_640_000_000;
+    const SPOT: u64 = 100_000_000; // $100
+    const LOT: u64 = 1_000; // POSITION_PRECISION
+    const FUTURE_INDEX: usize = SERIES_FUTURE_INDEX;
+
+    // Front expiry future marked at spot with a 5% maintenance margin.
+    fn setup() -> (Box<ZetaGroup>, Box<Greeks>, Box<MarginAccount>) {
+        let mut zeta_group = Box::new(ZetaGroup::zeroed());
+        let mut greeks = Box::new(Greeks::zeroed());
+        let margin_account = Box::new(MarginAccount::zeroed());
+        zeta_group.products[FUTURE_INDEX].kind = Kind::Future;
+        zeta_group.margin_parameters.future_margin_initial = 10_000_000;
+        zeta_group.margin_parameters.future_margin_maintenance = 5_000_000;
+        greeks.mark_prices[FUTURE_INDEX] = SPOT;
+        (zeta_group, greeks, margin_account)
+    }
+
+    #[test]
+    fn liquidatable_below_maintenance_margin() {
+        let (zeta_group, greeks, mut margin_account) = setup();
+        margin_account.positions[FUTURE_INDEX] = Position {
+            position: LOT as i64,
+            cost_of_trades: SPOT,
+            closing_orders: 0,
+            opening_orders: [0, 0],
+        };
+
+        // Maintenance margin of one lot is 5% of spot, pnl is flat.
+        margin_account.balance = SPOT / 20;
+        assert!(!margin_account
+            .is_liquidatable(&greeks, &zeta_group, SPOT)
+            .unwrap());
+        margin_account.balance = SPOT / 20 - 1;
+        assert!(margin_account
+            .is_liquidatable(&greeks, &zeta_group, SPOT)
+            .unwrap());
+    }
+
+    #[test]
+    fn series_tradeable_only_when_live_and_not_halted() {
+        let (mut zeta_group, _, _) = setup();
+        zeta_group.expiry_series[0] = ExpirySeries {
+            active_ts: NOW - 100,
+            expiry_ts: NOW + 100,
+            dirty: false,
+            padding: [0; 15],
+        };
+        assert!(zeta_group.validate_series_tradeable(0, NOW).is_err());
+        zeta_group.products[0].strike = Strike {
+            is_set: true,
+            value: SPOT,
+        };
+        assert!(zeta_group.validate_series_tradeable(0, NOW).is_ok());
+        assert!(zeta_group.validate_series_tradeable(0, NOW + 100).is_err());
+
+        zeta_group.halt_state.halted = true;
+        assert!(zeta_group.validate_series_tradeable(0, NOW).is_err());
+    }
+
+    #[test]
+    fn settlement_expiry_survives_reinitialised_slot() {
+        let (mut zeta_group, _, mut margin_account) = setup();
+        margin_account.series_expiry[0] = NOW - 100;
+        // Slot 0 has since been re-initialised for a new series.
+        zeta_group.expiry_series[0].expiry_ts = NOW + 100;
+
+        assert_eq!(
+            margin_account.get_settlement_expiry(0, NOW).unwrap(),
+            NOW - 100
+        );
+        assert!(margin_account.get_settlement_expiry(0, NOW - 101).is_err());
+        // Clean slot, nothing to settle.
+        assert!(margin_account.get_settlement_expiry(1, NOW).is_err());
+        let out_of_range = margin_account.series_expiry.len();
+        assert!(margin_account
+            .get_settlement_expiry(out_of_range, NOW)
+            .is_err());
+    }
+
+    #[test]
+    fn greeks_fresh_up_to_max_age() {
+        const MAX_AGE: u64 = 60;
+        let (_, mut greeks, _) = setup();
+        greeks.update_timestamp = [NOW - MAX_AGE, 0];
+        assert!(greeks.check_freshness(0, NOW, MAX_AGE).is_ok());
+        assert!(greeks.check_freshness(0, NOW + 1, MAX_AGE).is_err());
+    }
+
+    #[test]
+    fn greeks_stale_after_retreat_expiry() {
+        let (_, mut greeks, _) = setup();
+        greeks.update_timestamp = [NOW - 10, 0];
+        greeks.retreat_expiration_timestamp = [NOW - 5, 0];
+        assert!(greeks.check_freshness(0, NOW, 60).is_err());
+        greeks.retreat_expiration_timestamp = [NOW + 5, 0];
+        assert!(greeks.check_freshness(0, NOW, 60).is_ok());
+    }
+
+    #[test]
+    fn live_freshness_skips_series_not_live() {
+        let (mut zeta_group, mut greeks, _) = setup();
+        zeta_group.expiry_series[0] = ExpirySeries {
+            active_ts: NOW - 100,
+            expiry_ts: NOW + 100,
+            dirty: false,
+            padding: [0; 15],
+        };
+        // Expiry 1 is uninitialized and never updated.
+        greeks.update_timestamp = [NOW, 0];
+        assert!(greeks.check_live_freshness(&zeta_group, NOW, 0).is_ok());
+        assert!(greeks
+            .check_live_freshness(&zeta_group, NOW + 1, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn fill_crosses_long_to_short() {
+        let mut position = Position {
+            position: LOT as i64,
+            cost_of_trades: SPOT,
+            closing_orders: 0,
+            opening_orders: [0, 0],
+        };
+        // Sell 3 lots at $120: closes the long at a $20 profit, opens 2 short.
+        let realized_pnl = position
+            .apply_fill(Side::Ask, 3 * LOT, 120_000_000)
+            .unwrap();
+        assert_eq!(realized_pnl, 20_000_000);
+        assert!(position.position == -2 * LOT as i64);
+        assert!(position.cost_of_trades == 240_000_000);
+    }
+
+    #[test]
+    fn reserves_closing_before_opening_orders() {
+        let mut position = Position {
+            position: LOT as i64,
+            cost_of_trades: SPOT,
+            closing_orders: 0,
+            opening_orders: [0, 0],
+        };
+        position.apply_order(Side::Ask, 3 * LOT).unwrap();
+        assert!(position.closing_orders == LOT && position.opening_orders[1] == 2 * LOT);
+        // The long is already fully covered by resting asks.
+        position.apply_order(Side::Ask, LOT).unwrap();
+        assert!(position.closing_orders == LOT && position.opening_orders[1] == 3 * LOT);
+
+        // Fills are simulated as taker trades and leave resting orders as is.
+        assert_eq!(position.apply_fill(Side::Ask, LOT, SPOT).unwrap(), 0);
+        assert!(position.position == 0 && position.cost_of_trades == 0);
+        assert!(position.closing_orders == LOT && position.opening_orders[1] == 3 * LOT);
+    }
+
+    #[test]
+    fn projection_buffer_turns_negative() {
+        let (zeta_group, greeks, mut margin_account) = setup();
+        margin_account.balance = SPOT / 10;
+        let orders = [
+            SimulatedOrder {
+                market_index: FUTURE_INDEX,
+                side: Side::Bid,
+                size: LOT,
+                price: SPOT,
+                is_fill: true,
+            },
+            SimulatedOrder {
+                market_index: FUTURE_INDEX,
+                side: Side::Bid,
+                size: LOT,
+                price: SPOT,
+                is_fill: false,
+            },
+        ];
+        let projection = margin_account
+            .simulate_orders(&orders, &greeks, &zeta_group, SPOT)
+            .unwrap();
+
+        // 10% initial on the resting bid and 5% maintenance on the filled lot.
+        assert_eq!(
+            projection,
+            MarginProjection {
+                balance: (SPOT / 10) as i64,
+                initial_margin: SPOT / 10,
+                maintenance_margin: SPOT / 20,
+                unrealized_pnl: 0,
+                buffer: -((SPOT / 20) as i64),
+            }
+        );
+        assert!(!projection.is_sufficient());
+        // Nothing is written back.
+        let position = margin_account.positions[FUTURE_INDEX];
+        assert!(position.position == 0 && position.opening_orders[0] == 0);
+    }
+
+    #[test]
+    fn margin_strike_is_zero_for_futures() {
+        let (zeta_group, _, _) = setup();
+        assert_eq!(
+            zeta_group.products[FUTURE_INDEX].get_margin_strike(),
+            Some(0)
+        );
+
+        let mut option = zeta_group.products[0];
+        option.kind = Kind::Put;
+        assert_eq!(option.get_margin_strike(), None);
+        option.strike = Strike {
+            is_set: true,
+            value: SPOT,
+        };
+        assert_eq!(option.get_margin_strike(), Some(SPOT));
+    }
+}
@@ -0,0 +1,379 @@
+use crate::serum_market::get_market_parameters;
+use crate::serum_open_orders::get_open_orders;
+use crate::zeta_context::*;
+use crate::zeta_pda::*;
+use crate::*;
+use cpi_interface::global_interface;
+
+/// Zeta Program Client
+/// Defines a clean interface and set of helper functions to make CPI calls to the Zeta Program
+
+#[global_interface]
+pub trait ZetaInterface<'info, T: Accounts<'info>> {
+    fn initialize_margin_account(ctx: Context<T>, nonce: u8) -> ProgramResult;
+    fn deposit(ctx: Context<T>, amount: u64) -> ProgramResult;
+    fn withdraw(ctx: Context<T>, amount: u64) -> ProgramResult;
+    fn initialize_open_orders(ctx: Context<T>, nonce: u8, _map_nonce: u8) -> ProgramResult;
+    fn place_order(ctx: Context<T>, price: u64, size: u64, side: Side, client_order_id: Option<u64>) -> ProgramResult;
+    fn place_order_v2(ctx: Context<T>, price: u64, size: u64, side: Side, order_type: OrderType, client_order_id: Option<u64>) -> ProgramResult;
+    fn cancel_order(ctx: Context<T>, side: Side, order_id: u128) -> ProgramResult;
+    fn cancel_order_by_client_order_id(ctx: Context<T>, client_order_id: u64) -> ProgramResult;
+    fn settle_positions(ctx: Context<T>, expiry_ts: u64, settlement_nonce: u8) -> ProgramResult;
+    fn liquidate(ctx: Context<T>, size: u64) -> ProgramResult;
+    fn initialize_insurance_deposit_account(ctx: Context<T>, nonce: u8) -> ProgramResult;
+    fn deposit_insurance_vault(ctx: Context<T>, amount: u64) -> ProgramResult;
+    fn withdraw_insurance_vault(ctx: Context<T>, percentage_amount: u64) -> ProgramResult;
+    fn crank_event_queue(ctx: Context<T>) -> ProgramResult;
+    fn close_open_orders(ctx: Context<T>, _map_nonce: u8) -> ProgramResult;
+    fn close_margin_account(ctx: Context<T>) -> ProgramResult;
+}
+
+pub fn initialize_margin_account<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: InitializeMarginAccount<'info>,
+) -> ProgramResult {
+    let (_, nonce) = find_margin_account_address(
+        zeta_program.key,
+        cpi_accounts.zeta_group.key,
+        cpi_accounts.authority.key,
+    );
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::initialize_margin_account(cpi_ctx, nonce)
+}
+
+pub fn deposit<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: Deposit<'info>,
+    amount: u64,
+) -> ProgramResult {
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::deposit(cpi_ctx, amount)
+}
+
+pub fn withdraw<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: Withdraw<'info>,
+    amount: u64,
+) -> ProgramResult {
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::withdraw(cpi_ctx, amount)
+}
+
+pub fn initialize_open_orders<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: InitializeOpenOrders<'info>,
+) -> ProgramResult {
+    let (_, nonce) = find_open_orders_address(
+        zeta_program.key,
+        cpi_accounts.dex_program.key,
+        cpi_accounts.market.key,
+        cpi_accounts.authority.key,
+    );
+    let (_, map_nonce) =
+        find_open_orders_map_address(zeta_program.key, cpi_accounts.open_orders.key);
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::initialize_open_orders(cpi_ctx, nonce, map_nonce)
+}
+
+pub fn place_order<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: PlaceOrder<'info>,
+    price: u64,
+    size: u64,
+    side: Side,
+    client_order_id: Option<u64>,
+) -> ProgramResult {
+    validate_order(
+        &get_market_parameters(&cpi_accounts.market_accounts.market)?,
+        price,
+        size,
+        side,
+    )?;
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::place_order(cpi_ctx, price, size, side, client_order_id)
+}
+
+pub fn place_order_v2<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: PlaceOrder<'info>,
+    price: u64,
+    size: u64,
+    side: Side,
+    order_type: OrderType,
+    client_order_id: Option<u64>,
+) -> ProgramResult {
+    validate_order(
+        &get_market_parameters(&cpi_accounts.market_accounts.market)?,
+        price,
+        size,
+        side,
+    )?;
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::place_order_v2(cpi_ctx, price, size, side, order_type, client_order_id)
+}
+
+pub fn cancel_order<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: CancelOrder<'info>,
+    side: Side,
+    order_id: u128,
+) -> ProgramResult {
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::cancel_order(cpi_ctx, side, order_id)
+}
+
+pub fn cancel_order_by_client_order_id<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: CancelOrder<'info>,
+    client_order_id: u64,
+) -> ProgramResult {
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::cancel_order_by_client_order_id(cpi_ctx, client_order_id)
+}
+
+/// Cancels every order resting in `cancel_accounts.open_orders` one by one.
+/// Zeta's `cancel_all_market_orders` is admin only, so it can't be used here.
+pub fn cancel_all_orders<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: CancelOrder<'info>,
+) -> ProgramResult {
+    let orders = get_open_orders(&cpi_accounts.cancel_accounts.open_orders)?;
+    for order in orders {
+        cancel_order(
+            zeta_program.clone(),
+            cpi_accounts.clone(),
+            order.side,
+            order.order_id,
+        )?;
+    }
+    Ok(())
+}
+
+/// Settles the positions of `margin_accounts` in the series expiring at `expiry_ts`.
+pub fn settle_positions<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: SettlePositions<'info>,
+    margin_accounts: Vec<AccountInfo<'info>>,
+    expiry_ts: u64,
+) -> ProgramResult {
+    let underlying_mint = load_zeta_group(&cpi_accounts.zeta_group)?.underlying_mint;
+    let (settlement_address, settlement_nonce) =
+        find_settlement_address(zeta_program.key, &underlying_mint, expiry_ts);
+    if cpi_accounts.settlement_account.key != &settlement_address {
+        return wrap_error!(Err(ErrorCode::InvalidSettlementAccount.into()));
+    }
+    let cpi_ctx =
+        CpiContext::new(zeta_program, cpi_accounts).with_remaining_accounts(margin_accounts);
+    zeta_interface::settle_positions(cpi_ctx, expiry_ts, settlement_nonce)
+}
+
+/// Liquidates `size` lots of the liquidated account's position in `market`.
+pub fn liquidate<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: Liquidate<'info>,
+    size: u64,
+) -> ProgramResult {
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::liquidate(cpi_ctx, size)
+}
+
+pub fn initialize_insurance_deposit_account<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: InitializeInsuranceDepositAccount<'info>,
+) -> ProgramResult {
+    let (_, nonce) = find_user_insurance_deposit_address(
+        zeta_program.key,
+        cpi_accounts.zeta_group.key,
+        cpi_accounts.authority.key,
+    );
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::initialize_insurance_deposit_account(cpi_ctx, nonce)
+}
+
+pub fn deposit_insurance_vault<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: DepositInsuranceVault<'info>,
+    amount: u64,
+) -> ProgramResult {
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::deposit_insurance_vault(cpi_ctx, amount)
+}
+
+/// Withdraws `percentage_amount` (NATIVE_PRECISION_DENOMINATOR = 100%) of
+/// the authority's insurance fund deposit.
+pub fn withdraw_insurance_vault<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: WithdrawInsuranceVault<'info>,
+    percentage_amount: u64,
+) -> ProgramResult {
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::withdraw_insurance_vault(cpi_ctx, percentage_amount)
+}
+
+/// Processes up to EVENT_CRANK_LIMIT events from the market's event queue.
+///
+/// `remaining_accounts` are the open orders accounts referenced by the
+/// events, followed by their margin accounts in the same order.
+pub fn crank_event_queue<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: CrankEventQueue<'info>,
+    remaining_accounts: Vec<AccountInfo<'info>>,
+) -> ProgramResult {
+    let cpi_ctx =
+        CpiContext::new(zeta_program, cpi_accounts).with_remaining_accounts(remaining_accounts);
+    zeta_interface::crank_event_queue(cpi_ctx)
+}
+
+/// Closes the open orders account of a market, returning its rent to the authority.
+/// Fails if the margin account still has a position or orders in that market.
+pub fn close_open_orders<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: CloseOpenOrders<'info>,
+) -> ProgramResult {
+    {
+        let zeta_group = load_zeta_group(&cpi_accounts.zeta_group)?;
+        let margin_account = load_margin_account(&cpi_accounts.margin_account)?;
+        let market_index = zeta_group.get_product_index_by_key(cpi_accounts.market.key)?;
+        let position = margin_account.positions[market_index];
+        if position.position != 0 {
+            return wrap_error!(Err(ErrorCode::MarginAccountHasPositions.into()));
+        }
+        if position.has_active_orders() {
+            return wrap_error!(Err(ErrorCode::MarginAccountHasActiveOrders.into()));
+        }
+    }
+
+    let (_, map_nonce) =
+        find_open_orders_map_address(zeta_program.key, cpi_accounts.open_orders.key);
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::close_open_orders(cpi_ctx, map_nonce)
+}
+
+/// Closes the margin account, returning its rent to the authority.
+/// Fails if it still has any positions or orders.
+pub fn close_margin_account<'info>(
+    zeta_program: AccountInfo<'info>,
+    cpi_accounts: CloseMarginAccount<'info>,
+) -> ProgramResult {
+    {
+        let margin_account = load_margin_account(&cpi_accounts.margin_account)?;
+        if margin_account.has_positions() {
+            return wrap_error!(Err(ErrorCode::MarginAccountHasPositions.into()));
+        }
+        if margin_account.has_active_orders() {
+            return wrap_error!(Err(ErrorCode::MarginAccountHasActiveOrders.into()));
+        }
+    }
+
+    let cpi_ctx = CpiContext::new(zeta_program, cpi_accounts);
+    zeta_interface::close_margin_account(cpi_ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_spl::token::Token;
+
+    const TICK_SIZE: u64 = 100;
+    const LOT_SIZE: u64 = 10;
+
+    fn account(owner: Pubkey, data: Vec<u8>) -> AccountInfo<'static> {
+        AccountInfo::new(
+            Box::leak(Box::new(Pubkey::new_unique())),
+            false,
+            true,
+            Box::leak(Box::new(0)),
+            Box::leak(data.into_boxed_slice()),
+            Box::leak(Box::new(owner)),
+            false,
+            0,
+        )
+    }
+
+    // Serum market with the given lot sizes, laid out as in `serum_market`.
+    fn market() -> AccountInfo<'static> {
+        let mut data = vec![0u8; 5 + 376 + 7];
+        data[5 + 344..5 + 352].copy_from_slice(&LOT_SIZE.to_le_bytes());
+        data[5 + 352..5 + 360].copy_from_slice(&TICK_SIZE.to_le_bytes());
+        account(Pubkey::new_unique(), data)
+    }
+
+    fn place_order_accounts() -> PlaceOrder<'static> {
+        let mut authority = account(Pubkey::default(), vec![]);
+        authority.is_signer = true;
+        let mut token_program = account(Pubkey::default(), vec![]);
+        token_program.key = Box::leak(Box::new(Token::id()));
+        token_program.executable = true;
+        let mut rent = account(Pubkey::default(), vec![0u8; Rent::size_of()]);
+        rent.key = Box::leak(Box::new(anchor_lang::solana_program::sysvar::rent::ID));
+        Rent::default().to_account_info(&mut rent).unwrap();
+
+        let empty = || account(Pubkey::default(), vec![]);
+        PlaceOrder {
+            state: empty(),
+            zeta_group: empty(),
+            margin_account: empty(),
+            authority: Signer::try_from(&authority).unwrap(),
+            dex_program: empty(),
+            token_program: Program::try_from(&token_program).unwrap(),
+            serum_authority: empty(),
+            greeks: empty(),
+            open_orders: empty(),
+            rent: Sysvar::from_account_info(&rent).unwrap(),
+            market_accounts: MarketAccounts {
+                market: market(),
+                request_queue: empty(),
+                event_queue: empty(),
+                bids: empty(),
+                asks: empty(),
+                order_payer_token_account: empty(),
+                coin_vault: empty(),
+                pc_vault: empty(),
+                coin_wallet: empty(),
+                pc_wallet: empty(),
+            },
+            oracle: empty(),
+            market_node: empty(),
+            market_mint: empty(),
+            mint_authority: empty(),
+        }
+    }
+
+    // Whether v1 and v2 accept the order. Off-chain the CPI itself is a no-op
+    // stub, so only the validation can fail.
+    fn place(price: u64, size: u64, side: Side) -> [bool; 2] {
+        let zeta_program = account(Pubkey::default(), vec![]);
+        let v1 = place_order(
+            zeta_program.clone(),
+            place_order_accounts(),
+            price,
+            size,
+            side,
+            None,
+        );
+        let v2 = place_order_v2(
+            zeta_program,
+            place_order_accounts(),
+            price,
+            size,
+            side,
+            OrderType::Limit,
+            None,
+        );
+        [v1.is_ok(), v2.is_ok()]
+    }
+
+    #[test]
+    fn wrappers_validate_orders() {
+        assert_eq!(place(3 * TICK_SIZE, 2 * LOT_SIZE, Side::Bid), [true, true]);
+
+        assert_eq!(
+            place(TICK_SIZE, LOT_SIZE, Side::Uninitialized),
+            [false, false]
+        );
+        assert_eq!(place(0, LOT_SIZE, Side::Bid), [false, false]);
+        assert_eq!(place(TICK_SIZE + 1, LOT_SIZE, Side::Bid), [false, false]);
+        assert_eq!(place(TICK_SIZE, 0, Side::Ask), [false, false]);
+        assert_eq!(place(TICK_SIZE, LOT_SIZE + 1, Side::Ask), [false, false]);
+    }
+}
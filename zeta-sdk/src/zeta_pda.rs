@@ -0,0 +1,428 @@
+// Program derived addresses of the Zeta program's accounts.
+// `program_id` is the Zeta program, i.e. `zeta_sdk::ID`.
+use crate::zeta_constants::*;
+use crate::*;
+
+pub fn find_state_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STATE_SEED.as_bytes()], program_id)
+}
+
+pub fn find_underlying_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[UNDERLYING_SEED.as_bytes()], program_id)
+}
+
+pub fn find_zeta_group_address(program_id: &Pubkey, underlying_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ZETA_GROUP_SEED.as_bytes(), underlying_mint.as_ref()],
+        program_id,
+    )
+}
+
+pub fn find_greeks_address(program_id: &Pubkey, zeta_group: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GREEKS_SEED.as_bytes(), zeta_group.as_ref()], program_id)
+}
+
+pub fn find_market_indexes_address(program_id: &Pubkey, zeta_group: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MARKET_INDEXES_SEED.as_bytes(), zeta_group.as_ref()],
+        program_id,
+    )
+}
+
+/// Uninitialized serum market for `market_index`, before it is sorted into `ZetaGroup.products`.
+pub fn find_market_address(
+    program_id: &Pubkey,
+    zeta_group: &Pubkey,
+    market_index: u8,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MARKET_SEED.as_bytes(), zeta_group.as_ref(), &[market_index]],
+        program_id,
+    )
+}
+
+pub fn find_market_node_address(
+    program_id: &Pubkey,
+    zeta_group: &Pubkey,
+    market_index: u8,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            MARKET_NODE_SEED.as_bytes(),
+            zeta_group.as_ref(),
+            &[market_index],
+        ],
+        program_id,
+    )
+}
+
+/// Zeta's USDC collateral vault, `Deposit.vault` and `Withdraw.vault`.
+pub fn find_vault_address(program_id: &Pubkey, zeta_group: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED.as_bytes(), zeta_group.as_ref()], program_id)
+}
+
+pub fn find_zeta_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ZETA_VAULT_SEED.as_bytes(), mint.as_ref()], program_id)
+}
+
+/// Serum market vault holding `mint`, i.e. `MarketAccounts.coin_vault` or `pc_vault`.
+pub fn find_serum_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SERUM_VAULT_SEED.as_bytes(), mint.as_ref()], program_id)
+}
+
+pub fn find_socialized_loss_address(program_id: &Pubkey, zeta_group: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SOCIALIZED_LOSS_SEED.as_bytes(), zeta_group.as_ref()],
+        program_id,
+    )
+}
+
+/// Owner of all open orders accounts, `serum_authority` in the contexts.
+pub fn find_serum_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SERUM_SEED.as_bytes()], program_id)
+}
+
+pub fn find_mint_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_AUTH_SEED.as_bytes()], program_id)
+}
+
+pub fn find_base_mint_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BASE_MINT_SEED.as_bytes(), market.as_ref()], program_id)
+}
+
+pub fn find_quote_mint_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[QUOTE_MINT_SEED.as_bytes(), market.as_ref()], program_id)
+}
+
+pub fn find_margin_account_address(
+    program_id: &Pubkey,
+    zeta_group: &Pubkey,
+    authority: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            MARGIN_SEED.as_bytes(),
+            zeta_group.as_ref(),
+            authority.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+pub fn find_open_orders_address(
+    program_id: &Pubkey,
+    dex_program: &Pubkey,
+    market: &Pubkey,
+    authority: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            OPEN_ORDERS_SEED.as_bytes(),
+            dex_program.as_ref(),
+            market.as_ref(),
+            authority.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Maps an open orders account back to its owner.
+pub fn find_open_orders_map_address(program_id: &Pubkey, open_orders: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[open_orders.as_ref()], program_id)
+}
+
+/// Settlement account holding the settlement price of an underlying's
+/// series expiring at `expiry_ts`.
+pub fn find_settlement_address(
+    program_id: &Pubkey,
+    underlying_mint: &Pubkey,
+    expiry_ts: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SETTLEMENT_SEED.as_bytes(),
+            underlying_mint.as_ref(),
+            &expiry_ts.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Zeta's insurance fund token account for `zeta_group`.
+pub fn find_insurance_vault_address(program_id: &Pubkey, zeta_group: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ZETA_INSURANCE_VAULT_SEED.as_bytes(), zeta_group.as_ref()],
+        program_id,
+    )
+}
+
+/// Tracks `authority`'s share of the insurance fund.
+pub fn find_user_insurance_deposit_address(
+    program_id: &Pubkey,
+    zeta_group: &Pubkey,
+    authority: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            USER_INSURANCE_DEPOSIT_SEED.as_bytes(),
+            zeta_group.as_ref(),
+            authority.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Whitelist entry that allows `authority` to deposit into the insurance fund.
+pub fn find_whitelist_insurance_address(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[WHITELIST_INSURANCE_SEED.as_bytes(), authority.as_ref()],
+        program_id,
+    )
+}
+
+pub fn find_whitelist_trading_fees_address(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[WHITELIST_TRADING_FEES_SEED.as_bytes(), authority.as_ref()],
+        program_id,
+    )
+}
+
+/// PDAs of a `PlaceOrder`. The remaining fields are `dex_program`, `oracle`
+/// (`ZetaGroup.oracle`) and the Serum queues and order books, which are
+/// read from the Serum market.
+pub struct PlaceOrderAddresses {
+    pub state: Pubkey,
+    pub margin_account: Pubkey,
+    pub serum_authority: Pubkey,
+    pub greeks: Pubkey,
+    pub open_orders: Pubkey,
+    pub market_node: Pubkey,
+    pub market_mint: Pubkey,
+    pub mint_authority: Pubkey,
+    // `MarketAccounts`
+    pub order_payer_token_account: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_wallet: Pubkey,
+    pub pc_wallet: Pubkey,
+}
+
+/// Derives the `PlaceOrder` PDAs for `authority` on `market`.
+///
+/// # Arguments
+///
+/// * `market_index` - Index of `market` in `ZetaGroup.products`.
+/// * `side` - Order side, picks the mint Zeta mints into the order payer account.
+pub fn find_place_order_addresses(
+    program_id: &Pubkey,
+    dex_program: &Pubkey,
+    zeta_group: &Pubkey,
+    market: &Pubkey,
+    market_index: u8,
+    authority: &Pubkey,
+    side: Side,
+) -> Result<PlaceOrderAddresses> {
+    let (base_mint, _) = find_base_mint_address(program_id, market);
+    let (quote_mint, _) = find_quote_mint_address(program_id, market);
+    let (base_vault, _) = find_zeta_vault_address(program_id, &base_mint);
+    let (quote_vault, _) = find_zeta_vault_address(program_id, &quote_mint);
+    let (market_mint, order_payer_token_account) = match get_side_index(side)? {
+        0 => (quote_mint, quote_vault),
+        _ => (base_mint, base_vault),
+    };
+
+    Ok(PlaceOrderAddresses {
+        state: find_state_address(program_id).0,
+        margin_account: find_margin_account_address(program_id, zeta_group, authority).0,
+        serum_authority: find_serum_authority_address(program_id).0,
+        greeks: find_greeks_address(program_id, zeta_group).0,
+        open_orders: find_open_orders_address(program_id, dex_program, market, authority).0,
+        market_node: find_market_node_address(program_id, zeta_group, market_index).0,
+        market_mint,
+        mint_authority: find_mint_authority_address(program_id).0,
+        order_payer_token_account,
+        coin_vault: find_serum_vault_address(program_id, &base_mint).0,
+        pc_vault: find_serum_vault_address(program_id, &quote_mint).0,
+        coin_wallet: base_vault,
+        pc_wallet: quote_vault,
+    })
+}
+
+/// PDAs of `CancelAccounts`. `dex_program`, the order books and the event
+/// queue are read from the Serum market.
+pub struct CancelAddresses {
+    pub state: Pubkey,
+    pub margin_account: Pubkey,
+    pub serum_authority: Pubkey,
+    pub open_orders: Pubkey,
+}
+
+/// Derives the `CancelAccounts` PDAs for `authority` on `market`.
+pub fn find_cancel_addresses(
+    program_id: &Pubkey,
+    dex_program: &Pubkey,
+    zeta_group: &Pubkey,
+    market: &Pubkey,
+    authority: &Pubkey,
+) -> CancelAddresses {
+    CancelAddresses {
+        state: find_state_address(program_id).0,
+        margin_account: find_margin_account_address(program_id, zeta_group, authority).0,
+        serum_authority: find_serum_authority_address(program_id).0,
+        open_orders: find_open_orders_address(program_id, dex_program, market, authority).0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // Expected addresses were derived independently of this module from the
+    // seed layouts, against the devnet program id. Program-only seeds (state,
+    // serum and mint authority) therefore pin the devnet program's singletons,
+    // the others use fixed keys.
+    const DEVNET_PROGRAM_ID: &str = "BG3oRikW8d16YjUEmX3ZxHm9SiJzrGtMhsSR8aCw1Cd7";
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn program_id() -> Pubkey {
+        Pubkey::from_str(DEVNET_PROGRAM_ID).unwrap()
+    }
+
+    fn assert_address(address: Pubkey, expected: &str) {
+        assert_eq!(address.to_string(), expected);
+    }
+
+    #[test]
+    fn derives_program_seeds() {
+        let program_id = program_id();
+        assert_address(
+            find_state_address(&program_id).0,
+            "9VddCF6iEyZbjkCQ4g8VJpjEtuLsgmvRCc6LQwAvXigC",
+        );
+        assert_address(
+            find_serum_authority_address(&program_id).0,
+            "7m134nU79ezr3b7jYSehnhto94AvSP4yexa3Wdhxff99",
+        );
+        assert_address(
+            find_mint_authority_address(&program_id).0,
+            "278LYD3sfoYF9tbcYf2HatzGYGu8VuQ7SjpSFwAgGMY2",
+        );
+    }
+
+    #[test]
+    fn derives_keyed_seeds() {
+        let program_id = program_id();
+        let (zeta_group, underlying_mint) = (key(1), key(5));
+        assert_address(
+            find_zeta_group_address(&program_id, &underlying_mint).0,
+            "34R2i3DMo2z1WJfzSh2qVGsozyNtZeTFtm8H72euNx7C",
+        );
+        assert_address(
+            find_greeks_address(&program_id, &zeta_group).0,
+            "BcSJn9qJ3RCJLn94jRmtF3q1LaSAQvUZ8PXuaef7sKe7",
+        );
+        assert_address(
+            find_settlement_address(&program_id, &underlying_mint, 1_640_000_000).0,
+            "EWF6otnibXTUwdHpEN7MEBFBeZBCRv4mtbqgeoPgyHZx",
+        );
+        assert_address(
+            find_open_orders_map_address(
+                &program_id,
+                &Pubkey::from_str("D5adGE8H6Wf27f7ijJFrbVS8sw4C8s2oKdkkSv4Aqr9S").unwrap(),
+            )
+            .0,
+            "GePn3gz6dwkNBcQ3wPD5JZwm5MvWsZoKighZi3j7pDKU",
+        );
+    }
+
+    #[test]
+    fn derives_place_order_addresses() {
+        let program_id = program_id();
+        let (zeta_group, authority, market, dex_program) = (key(1), key(2), key(3), key(4));
+        let addresses = find_place_order_addresses(
+            &program_id,
+            &dex_program,
+            &zeta_group,
+            &market,
+            7,
+            &authority,
+            Side::Bid,
+        )
+        .unwrap();
+        assert_address(
+            addresses.margin_account,
+            "69cTpTapDA2Hw5CWYS9xi8bKsXZSrH4tEG9cbGxPFiJk",
+        );
+        assert_address(
+            addresses.open_orders,
+            "D5adGE8H6Wf27f7ijJFrbVS8sw4C8s2oKdkkSv4Aqr9S",
+        );
+        assert_address(
+            addresses.market_node,
+            "2abxNjdD45AzdhfxEjoLwiL7Wp76dCjfqM1EDxkyz4Xg",
+        );
+        // Bids pay in the quote mint.
+        assert_address(
+            addresses.market_mint,
+            "DhKoaBYmBguFHTmnjmBtyqye9Pe4pvqHvdQfDaDQXPpM",
+        );
+        assert_address(
+            addresses.order_payer_token_account,
+            "4WboaUAWZTUj11njozhEeiWd8thYrnQFmp46iKjGZvH6",
+        );
+        assert_address(
+            addresses.coin_vault,
+            "4aK6o6MWCC8PenRTyDLUJRAz47pVXk2ZvEyu4C6bZDdr",
+        );
+        assert_address(
+            addresses.pc_vault,
+            "9N4VWiKdwG5NVvi3Ehcsy2p6o6X8gzAXa9esq2CXrm54",
+        );
+        assert_address(
+            addresses.coin_wallet,
+            "DGUWSSoVd7gEj6Lwata9C3t3e5Mr9J88cCnNhJkQu24V",
+        );
+
+        let ask = find_place_order_addresses(
+            &program_id,
+            &dex_program,
+            &zeta_group,
+            &market,
+            7,
+            &authority,
+            Side::Ask,
+        )
+        .unwrap();
+        assert_address(
+            ask.market_mint,
+            "5b3pznd2PZGA2rypJ2XsUtzhi4v7dzAdLa5qG9qwMxrp",
+        );
+        assert_address(
+            ask.order_payer_token_account,
+            "DGUWSSoVd7gEj6Lwata9C3t3e5Mr9J88cCnNhJkQu24V",
+        );
+    }
+
+    #[test]
+    fn cancel_addresses_match_place_order() {
+        let program_id = program_id();
+        let (zeta_group, authority, market, dex_program) = (key(1), key(2), key(3), key(4));
+        let cancel =
+            find_cancel_addresses(&program_id, &dex_program, &zeta_group, &market, &authority);
+        assert_address(
+            cancel.margin_account,
+            "69cTpTapDA2Hw5CWYS9xi8bKsXZSrH4tEG9cbGxPFiJk",
+        );
+        assert_address(
+            cancel.open_orders,
+            "D5adGE8H6Wf27f7ijJFrbVS8sw4C8s2oKdkkSv4Aqr9S",
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use crate::*;
+
+/// Net greeks of a group of positions.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PortfolioGreeks {
+    // Units of underlying at PRICING_PRECISION, positive when long.
+    pub delta: i128,
+    // Sum of position * vega, negative when short volatility.
+    pub vega: Decimal,
+    // Gross notional at spot, PLATFORM_PRECISION.
+    pub notional: u128,
+}
+
+impl PortfolioGreeks {
+    fn add(&mut self, other: &PortfolioGreeks) -> Result<()> {
+        self.delta = self
+            .delta
+            .checked_add(other.delta)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.vega = self
+            .vega
+            .checked_add(other.vega)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.notional = self
+            .notional
+            .checked_add(other.notional)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Portfolio {
+    pub expiries: [PortfolioGreeks; ACTIVE_EXPIRIES],
+    pub total: PortfolioGreeks,
+}
+
+/// Aggregates the greeks of every position in `margin_account`, per expiry
+/// and in total.
+///
+/// # Arguments
+///
+/// * `spot` - Oracle price (6.dp), used for notional.
+pub fn get_portfolio_greeks(
+    margin_account: &MarginAccount,
+    zeta_group: &ZetaGroup,
+    greeks: &Greeks,
+    spot: u64,
+) -> Result<Portfolio> {
+    let mut portfolio = Portfolio::default();
+    for expiry_index in 0..ACTIVE_EXPIRIES {
+        let expiry_greeks =
+            get_expiry_greeks(margin_account, zeta_group, greeks, spot, expiry_index)?;
+        portfolio.total.add(&expiry_greeks)?;
+        portfolio.expiries[expiry_index] = expiry_greeks;
+    }
+    Ok(portfolio)
+}
+
+/// Aggregates the greeks of the positions in a single expiry series.
+pub fn get_expiry_greeks(
+    margin_account: &MarginAccount,
+    zeta_group: &ZetaGroup,
+    greeks: &Greeks,
+    spot: u64,
+    expiry_index: usize,
+) -> Result<PortfolioGreeks> {
+    let positions = margin_account.get_positions_slice(expiry_index);
+    let products = zeta_group.get_products_slice(expiry_index);
+
+    let mut expiry_greeks = PortfolioGreeks::default();
+    for (i, position) in positions.iter().enumerate() {
+        if position.position == 0 {
+            continue;
+        }
+
+        let market_index = get_products_slice_market_index(expiry_index, i);
+        let delta = greeks.get_delta(market_index, products[i].kind)?;
+        let vega = greeks.get_vega(market_index, products[i].kind)?;
+
+        let size = position.position as i128;
+        let lots = Decimal::from_i128_with_scale(size, POSITION_PRECISION);
+        expiry_greeks.add(&PortfolioGreeks {
+            delta: size
+                .checked_mul(delta)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(POSITION_PRECISION_DENOMINATOR as i128)
+                .ok_or(ErrorCode::MathOverflow)?,
+            vega: lots.checked_mul(vega).ok_or(ErrorCode::MathOverflow)?,
+            notional: size
+                .unsigned_abs()
+                .checked_mul(spot as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(POSITION_PRECISION_DENOMINATOR)
+                .ok_or(ErrorCode::MathOverflow)?,
+        })?;
+    }
+    Ok(expiry_greeks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    const SPOT: u64 = 100_000_000;
+    const LOT: i64 = 1_000;
+    const PUT_INDEX: usize = NUM_STRIKES;
+    const BACK_FUTURE_INDEX: usize = NUM_PRODUCTS_PER_SERIES + SERIES_FUTURE_INDEX;
+
+    // Front expiry: long 2 calls and short 1 put of the same strike, call
+    // delta 0.6 and vega 0.1. Back expiry: short 1 future.
+    fn setup() -> (Box<ZetaGroup>, Box<Greeks>, Box<MarginAccount>) {
+        let mut zeta_group = Box::new(ZetaGroup::zeroed());
+        let mut greeks = Box::new(Greeks::zeroed());
+        let mut margin_account = Box::new(MarginAccount::zeroed());
+
+        let strike = Strike {
+            is_set: true,
+            value: SPOT,
+        };
+        for (market_index, kind) in [
+            (0, Kind::Call),
+            (PUT_INDEX, Kind::Put),
+            (BACK_FUTURE_INDEX, Kind::Future),
+        ] {
+            zeta_group.products[market_index] = Product {
+                market: Pubkey::default(),
+                strike,
+                dirty: false,
+                kind,
+            };
+        }
+        zeta_group.margin_parameters.future_margin_maintenance = 5_000_000;
+        greeks.product_greeks[0] = ProductGreeks {
+            delta: 600_000_000_000,
+            vega: Decimal::new(1, 1).into(),
+            volatility: Decimal::new(5, 1).into(),
+        };
+
+        for (market_index, size) in [(0, 2 * LOT), (PUT_INDEX, -LOT), (BACK_FUTURE_INDEX, -LOT)] {
+            margin_account.positions[market_index] = Position {
+                position: size,
+                cost_of_trades: 0,
+                closing_orders: 0,
+                opening_orders: [0, 0],
+            };
+        }
+        (zeta_group, greeks, margin_account)
+    }
+
+    #[test]
+    fn aggregates_greeks_per_expiry_and_total() {
+        let (zeta_group, greeks, margin_account) = setup();
+        let portfolio = get_portfolio_greeks(&margin_account, &zeta_group, &greeks, SPOT).unwrap();
+
+        // 2 * 0.6 - 1 * (0.6 - 1) = 1.6
+        assert_eq!(
+            portfolio.expiries[0],
+            PortfolioGreeks {
+                delta: 1_600_000_000_000,
+                vega: Decimal::new(1, 1),
+                notional: 3 * SPOT as u128,
+            }
+        );
+        assert_eq!(
+            portfolio.expiries[1],
+            PortfolioGreeks {
+                delta: -1_000_000_000_000,
+                vega: Decimal::zero(),
+                notional: SPOT as u128,
+            }
+        );
+        assert_eq!(
+            portfolio.total,
+            PortfolioGreeks {
+                delta: 600_000_000_000,
+                vega: Decimal::new(1, 1),
+                notional: 4 * SPOT as u128,
+            }
+        );
+    }
+
+    #[test]
+    fn margin_matches_fixture() {
+        let (zeta_group, greeks, margin_account) = setup();
+        // Options margin parameters are zero, only the short future is
+        // margined at 5% of spot.
+        assert_eq!(
+            margin_account
+                .get_maintenance_margin(&greeks, &zeta_group, SPOT)
+                .unwrap(),
+            SPOT / 20
+        );
+        assert_eq!(
+            margin_account
+                .get_initial_margin(&greeks, &zeta_group, SPOT)
+                .unwrap(),
+            0
+        );
+    }
+}
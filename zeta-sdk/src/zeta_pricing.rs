@@ -0,0 +1,331 @@
+// Black-Scholes pricing of Zeta options from the inputs Zeta publishes
+// on-chain, without dividends.
+use crate::*;
+use std::convert::TryInto;
+
+const DAYS_IN_A_YEAR: u64 = 365;
+// Largest |x| passed to `exp`, e^50 ~ 5e21 is well within Decimal range.
+const MAX_EXP_ARG: u64 = 50;
+// Past |x| = 8 the normal cdf is 0 or 1 and the pdf 0 to within 1e-14.
+const NORMAL_BOUND: u64 = 8;
+
+/// Theoretical price and greeks of a single option.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OptionPricing {
+    pub price: u64, // PLATFORM_PRECISION
+    pub delta: i64, // PRICING_PRECISION, negative for puts
+    // Change in delta per 1 USDC move in spot.
+    pub gamma: Decimal,
+    // Change in price (USDC) per 1 volatility point.
+    pub vega: Decimal,
+    // Change in price (USDC) per day, usually negative.
+    pub theta: Decimal,
+}
+
+/// Prices a call or put.
+///
+/// # Arguments
+///
+/// * `spot` - Underlying price (6.dp).
+/// * `strike` - Strike (6.dp).
+/// * `time_to_expiry` - Seconds until expiry.
+/// * `interest_rate` - Annualised rate (12.dp), as stored in `Greeks.interest_rate`.
+/// * `volatility` - Annualised volatility as a fraction, as stored in `ProductGreeks.volatility`.
+pub fn black_scholes(
+    spot: u64,
+    strike: u64,
+    time_to_expiry: u64,
+    interest_rate: i64,
+    volatility: Decimal,
+    kind: Kind,
+) -> Result<OptionPricing> {
+    if spot == 0 || strike == 0 || time_to_expiry == 0 || volatility <= Decimal::zero() {
+        return wrap_error!(Err(ErrorCode::InvalidPricingInputs.into()));
+    }
+    let is_call = match kind {
+        Kind::Call => true,
+        Kind::Put => false,
+        _ => return wrap_error!(Err(ErrorCode::InvalidOptionKind.into())),
+    };
+
+    let s = Decimal::from_i128_with_scale(spot as i128, PLATFORM_PRECISION);
+    let k = Decimal::from_i128_with_scale(strike as i128, PLATFORM_PRECISION);
+    let r = Decimal::new(interest_rate, PRICING_PRECISION);
+    let t = div(
+        Decimal::from(time_to_expiry),
+        Decimal::from(SECONDS_IN_A_YEAR),
+    )?;
+    let two = Decimal::from(2);
+
+    let sqrt_t = t.sqrt().ok_or(ErrorCode::InvalidPricingInputs)?;
+    let vol_sqrt_t = mul(volatility, sqrt_t)?;
+    // `ln` overflows for tiny arguments, so only take it of a ratio >= 1. The
+    // ratio is at most u64::MAX, which it handles.
+    let log_moneyness = if s >= k {
+        div(s, k)?.ln()
+    } else {
+        -div(k, s)?.ln()
+    };
+    let drift = mul(add(r, div(mul(volatility, volatility)?, two)?)?, t)?;
+    let d1 = div(add(log_moneyness, drift)?, vol_sqrt_t)?;
+    let d2 = sub(d1, vol_sqrt_t)?;
+    let rate_time = mul(r, t)?;
+    // `exp` can overflow internally well before its result would.
+    if rate_time.abs() > Decimal::from(MAX_EXP_ARG) {
+        return wrap_error!(Err(ErrorCode::InvalidPricingInputs.into()));
+    }
+    let discount = (-rate_time).checked_exp().ok_or(ErrorCode::MathOverflow)?;
+    let discounted_strike = mul(k, discount)?;
+    let pdf_d1 = norm_pdf(d1)?;
+
+    let (price, delta, rate_theta) = if is_call {
+        let cdf_d1 = norm_cdf(d1)?;
+        let cdf_d2 = norm_cdf(d2)?;
+        (
+            sub(mul(s, cdf_d1)?, mul(discounted_strike, cdf_d2)?)?,
+            cdf_d1,
+            -mul(mul(r, discounted_strike)?, cdf_d2)?,
+        )
+    } else {
+        let cdf_neg_d2 = norm_cdf(-d2)?;
+        (
+            sub(mul(discounted_strike, cdf_neg_d2)?, mul(s, norm_cdf(-d1)?)?)?,
+            sub(norm_cdf(d1)?, Decimal::one())?,
+            mul(mul(r, discounted_strike)?, cdf_neg_d2)?,
+        )
+    };
+    let gamma = div(pdf_d1, mul(s, vol_sqrt_t)?)?;
+    let vega = div(mul(mul(s, pdf_d1)?, sqrt_t)?, Decimal::from(100))?;
+    let time_theta = -div(mul(mul(s, pdf_d1)?, volatility)?, mul(two, sqrt_t)?)?;
+    let theta = div(add(time_theta, rate_theta)?, Decimal::from(DAYS_IN_A_YEAR))?;
+
+    Ok(OptionPricing {
+        // Deep out of the money options can round to a tiny negative price.
+        price: to_native(price.max(Decimal::zero()), PLATFORM_PRECISION)?
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow)?,
+        delta: to_native(delta, PRICING_PRECISION)?
+            .try_into()
+            .map_err(|_| ErrorCode::MathOverflow)?,
+        gamma,
+        vega,
+        theta,
+    })
+}
+
+/// Prices the option at `market_index` from the zeta group and greeks accounts.
+pub fn price_market(
+    zeta_group: &ZetaGroup,
+    greeks: &Greeks,
+    market_index: usize,
+    spot: u64,
+    current_ts: u64,
+) -> Result<OptionPricing> {
+    let product = &zeta_group.products[market_index];
+    let expiry_index = market_index / NUM_PRODUCTS_PER_SERIES;
+    let product_index = market_index % NUM_PRODUCTS_PER_SERIES;
+    if product_index == SERIES_FUTURE_INDEX {
+        return wrap_error!(Err(ErrorCode::InvalidOptionKind.into()));
+    }
+
+    let expiry_ts = zeta_group.expiry_series[expiry_index].expiry_ts;
+    if current_ts >= expiry_ts {
+        return wrap_error!(Err(ErrorCode::InvalidPricingInputs.into()));
+    }
+
+    // Calls and puts of the same strike share their product greeks.
+    let product_greeks = greeks.get_product_greeks_slice(expiry_index)[product_index % NUM_STRIKES];
+    black_scholes(
+        spot,
+        product.strike.get_strike()?,
+        expiry_ts - current_ts,
+        greeks.interest_rate[expiry_index],
+        Decimal::from(product_greeks.volatility),
+        product.kind,
+    )
+}
+
+/// Errors if `mark_price` deviates from `theo_price` by more than
+/// `max_deviation_pct` (NATIVE_PRECISION_DENOMINATOR = 100%) of the theo price.
+pub fn check_mark_price(mark_price: u64, theo_price: u64, max_deviation_pct: u64) -> Result<()> {
+    let deviation = (mark_price as i128 - theo_price as i128).unsigned_abs();
+    let max_deviation = (theo_price as u128)
+        .checked_mul(max_deviation_pct as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(NATIVE_PRECISION_DENOMINATOR)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if deviation > max_deviation {
+        msg!(
+            "Mark price {} deviates from theo price {} by more than {}",
+            mark_price,
+            theo_price,
+            max_deviation
+        );
+        return wrap_error!(Err(ErrorCode::MarkPriceDeviation.into()));
+    }
+    Ok(())
+}
+
+fn to_native(value: Decimal, precision: u32) -> Result<i128> {
+    mul(value, Decimal::from(10u64.pow(precision)))?
+        .round()
+        .to_i128()
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+// Normal cdf that saturates instead of overflowing, `Decimal::norm_cdf`
+// overflows for |x| above ~9.
+fn norm_cdf(x: Decimal) -> Result<Decimal> {
+    let bound = Decimal::from(NORMAL_BOUND);
+    if x > bound {
+        Ok(Decimal::one())
+    } else if x < -bound {
+        Ok(Decimal::zero())
+    } else {
+        Ok(x.norm_cdf())
+    }
+}
+
+fn norm_pdf(x: Decimal) -> Result<Decimal> {
+    if x.abs() > Decimal::from(NORMAL_BOUND) {
+        return Ok(Decimal::zero());
+    }
+    x.checked_norm_pdf().ok_or(ErrorCode::MathOverflow.into())
+}
+
+fn add(a: Decimal, b: Decimal) -> Result<Decimal> {
+    a.checked_add(b).ok_or(ErrorCode::MathOverflow.into())
+}
+
+fn sub(a: Decimal, b: Decimal) -> Result<Decimal> {
+    a.checked_sub(b).ok_or(ErrorCode::MathOverflow.into())
+}
+
+fn mul(a: Decimal, b: Decimal) -> Result<Decimal> {
+    a.checked_mul(b).ok_or(ErrorCode::MathOverflow.into())
+}
+
+fn div(a: Decimal, b: Decimal) -> Result<Decimal> {
+    a.checked_div(b).ok_or(ErrorCode::MathOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    // Reference values for S = K = 100, r = 0, vol = 20%, t = 1y.
+    const SPOT: u64 = 100_000_000;
+    const TOLERANCE: u64 = 10_000; // 1 cent.
+
+    #[test]
+    fn prices_call() {
+        let pricing = black_scholes(
+            SPOT,
+            SPOT,
+            SECONDS_IN_A_YEAR,
+            0,
+            Decimal::new(2, 1),
+            Kind::Call,
+        )
+        .unwrap();
+        assert!((pricing.price as i64 - 7_965_567).unsigned_abs() < TOLERANCE);
+        assert!((pricing.delta - 539_827_837_277).unsigned_abs() < 1_000_000_000);
+    }
+
+    #[test]
+    fn put_call_parity_holds() {
+        let call = black_scholes(
+            SPOT,
+            SPOT,
+            SECONDS_IN_A_YEAR,
+            0,
+            Decimal::new(2, 1),
+            Kind::Call,
+        )
+        .unwrap();
+        let put = black_scholes(
+            SPOT,
+            SPOT,
+            SECONDS_IN_A_YEAR,
+            0,
+            Decimal::new(2, 1),
+            Kind::Put,
+        )
+        .unwrap();
+        assert!((call.price as i64 - put.price as i64).unsigned_abs() < TOLERANCE);
+        assert!((call.delta - put.delta - 10i64.pow(PRICING_PRECISION)).unsigned_abs() < 1_000_000);
+        assert_eq!(call.gamma.round_dp(6), put.gamma.round_dp(6));
+    }
+
+    #[test]
+    fn rejects_future() {
+        assert!(black_scholes(
+            SPOT,
+            SPOT,
+            SECONDS_IN_A_YEAR,
+            0,
+            Decimal::new(2, 1),
+            Kind::Future
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn checks_mark_price_deviation() {
+        // 5% tolerance.
+        assert!(check_mark_price(104_000_000, 100_000_000, 5_000_000).is_ok());
+        assert!(check_mark_price(106_000_000, 100_000_000, 5_000_000).is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_inputs() {
+        assert!(black_scholes(SPOT, SPOT, u64::MAX, 0, Decimal::MAX, Kind::Call).is_err());
+        // Deep out of the money, d1 and d2 are far outside the normal bound.
+        assert!(black_scholes(1, u64::MAX, 1, i64::MAX, Decimal::new(2, 1), Kind::Put).is_ok());
+    }
+
+    #[test]
+    fn prices_market_from_accounts() {
+        let now = 1_640_000_000;
+        let mut zeta_group = Box::new(ZetaGroup::zeroed());
+        let mut greeks = Box::new(Greeks::zeroed());
+        zeta_group.expiry_series[0].expiry_ts = now + SECONDS_IN_A_YEAR;
+        zeta_group.products[NUM_STRIKES] = Product {
+            market: Pubkey::default(),
+            strike: Strike {
+                is_set: true,
+                value: SPOT,
+            },
+            dirty: false,
+            kind: Kind::Put,
+        };
+        greeks.product_greeks[0].volatility = Decimal::new(2, 1).into();
+
+        // The put shares the product greeks of the call at the same strike.
+        let pricing = price_market(&zeta_group, &greeks, NUM_STRIKES, SPOT, now).unwrap();
+        assert_eq!(
+            pricing,
+            black_scholes(
+                SPOT,
+                SPOT,
+                SECONDS_IN_A_YEAR,
+                0,
+                Decimal::new(2, 1),
+                Kind::Put
+            )
+            .unwrap()
+        );
+        assert!((pricing.price as i64 - 7_965_567).unsigned_abs() < TOLERANCE);
+
+        assert!(price_market(
+            &zeta_group,
+            &greeks,
+            NUM_STRIKES,
+            SPOT,
+            now + SECONDS_IN_A_YEAR
+        )
+        .is_err());
+        assert!(price_market(&zeta_group, &greeks, SERIES_FUTURE_INDEX, SPOT, now).is_err());
+    }
+}
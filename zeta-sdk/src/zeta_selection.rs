@@ -0,0 +1,181 @@
+use crate::*;
+
+/// Returns the market index of the live option that best matches a target
+/// tenor and absolute delta.
+///
+/// Expiries are ranked by how close their time to expiry is to `target_tenor`,
+/// then strikes within an expiry by how close their absolute delta is to
+/// `target_delta`. Unset strikes, dirty products and series that are not
+/// `Live` at `current_ts` are skipped.
+///
+/// # Arguments
+///
+/// * `current_ts` - Unix timestamp the selection is evaluated at.
+/// * `target_tenor` - Desired time to expiry in seconds.
+/// * `target_delta` - Desired absolute delta (PRICING_PRECISION).
+/// * `kind` - `Kind::Call` or `Kind::Put`.
+pub fn select_market_index(
+    zeta_group: &ZetaGroup,
+    greeks: &Greeks,
+    current_ts: u64,
+    target_tenor: u64,
+    target_delta: u64,
+    kind: Kind,
+) -> Result<usize> {
+    let strike_offset = match kind {
+        Kind::Call => 0,
+        Kind::Put => NUM_STRIKES,
+        _ => return wrap_error!(Err(ErrorCode::InvalidOptionKind.into())),
+    };
+
+    let mut best: Option<(u64, u64, usize)> = None;
+    for (expiry_index, expiry_series) in zeta_group.expiry_series.iter().enumerate() {
+        if expiry_series.get_status(current_ts) != ExpirySeriesStatus::Live {
+            continue;
+        }
+        let tenor_diff = unsigned_abs_diff(expiry_series.expiry_ts - current_ts, target_tenor);
+
+        let products = zeta_group.get_products_slice(expiry_index);
+        let product_greeks = greeks.get_product_greeks_slice(expiry_index);
+        for strike_index in 0..NUM_STRIKES {
+            let product = &products[strike_offset + strike_index];
+            if !product.strike.is_set() || product.dirty || product.kind != kind {
+                continue;
+            }
+            let delta = get_absolute_delta(product_greeks[strike_index].delta, kind)?;
+            let delta_diff = unsigned_abs_diff(delta, target_delta);
+
+            let candidate = (
+                tenor_diff,
+                delta_diff,
+                get_products_slice_market_index(expiry_index, strike_offset + strike_index),
+            );
+            if best.map_or(true, |b| (candidate.0, candidate.1) < (b.0, b.1)) {
+                best = Some(candidate);
+            }
+        }
+    }
+
+    match best {
+        Some((_, _, market_index)) => Ok(market_index),
+        None => wrap_error!(Err(ErrorCode::NoMatchingMarket.into())),
+    }
+}
+
+/// Returns the absolute delta of an option (PRICING_PRECISION).
+/// Greeks store the call delta per strike, the put delta is `call_delta - 1`.
+pub fn get_absolute_delta(call_delta: u64, kind: Kind) -> Result<u64> {
+    match kind {
+        Kind::Call => Ok(call_delta),
+        Kind::Put => Ok(10u64.pow(PRICING_PRECISION).saturating_sub(call_delta)),
+        _ => wrap_error!(Err(ErrorCode::InvalidOptionKind.into())),
+    }
+}
+
+fn unsigned_abs_diff(a: u64, b: u64) -> u64 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    const NOW: u64 = 1_640_000_000;
+    const DAY: u64 = 86_400;
+
+    fn delta(pct: u64) -> u64 {
+        pct * 10u64.pow(PRICING_PRECISION) / 100
+    }
+
+    // Two live expiries (1 and 8 days out), strikes 100..=110 and call
+    // deltas decreasing from 95 to 45 in steps of 5.
+    fn setup() -> (Box<ZetaGroup>, Box<Greeks>) {
+        let mut zeta_group = Box::new(ZetaGroup::zeroed());
+        let mut greeks = Box::new(Greeks::zeroed());
+
+        for (expiry_index, days) in [1, 8].iter().enumerate() {
+            zeta_group.expiry_series[expiry_index] = ExpirySeries {
+                active_ts: NOW - DAY,
+                expiry_ts: NOW + days * DAY,
+                dirty: false,
+                padding: [0; 15],
+            };
+            for i in 0..NUM_PRODUCTS_PER_SERIES {
+                let market_index = get_products_slice_market_index(expiry_index, i);
+                let kind = if i < NUM_STRIKES {
+                    Kind::Call
+                } else if i < NUM_STRIKES * 2 {
+                    Kind::Put
+                } else {
+                    Kind::Future
+                };
+                zeta_group.products[market_index].kind = kind;
+                zeta_group.products[market_index].strike = Strike {
+                    is_set: kind != Kind::Future,
+                    value: (100 + (i % NUM_STRIKES) as u64) * 10u64.pow(PLATFORM_PRECISION),
+                };
+            }
+            for i in 0..NUM_STRIKES {
+                greeks.product_greeks[expiry_index * NUM_STRIKES + i].delta =
+                    delta(95 - 5 * i as u64);
+            }
+        }
+        (zeta_group, greeks)
+    }
+
+    #[test]
+    fn selects_closest_tenor_and_delta() {
+        let (zeta_group, greeks) = setup();
+        let market_index =
+            select_market_index(&zeta_group, &greeks, NOW, 7 * DAY, delta(60), Kind::Call).unwrap();
+        // 8 day expiry, call delta 60 is strike index 7.
+        assert_eq!(market_index, NUM_PRODUCTS_PER_SERIES + 7);
+    }
+
+    #[test]
+    fn selects_puts_by_absolute_delta() {
+        let (zeta_group, greeks) = setup();
+        let market_index =
+            select_market_index(&zeta_group, &greeks, NOW, DAY, delta(20), Kind::Put).unwrap();
+        // 1 day expiry, call delta 80 => put delta 20 is strike index 3.
+        assert_eq!(market_index, NUM_STRIKES + 3);
+    }
+
+    #[test]
+    fn skips_non_live_series_and_unset_strikes() {
+        let (mut zeta_group, greeks) = setup();
+        zeta_group.expiry_series[1].dirty = true;
+        zeta_group.products[7].strike.is_set = false;
+        let market_index =
+            select_market_index(&zeta_group, &greeks, NOW, 7 * DAY, delta(60), Kind::Call).unwrap();
+        // Falls back to the 1 day expiry and the next closest delta.
+        assert_eq!(market_index, 6);
+    }
+
+    #[test]
+    fn rejects_when_no_series_is_live() {
+        let (zeta_group, greeks) = setup();
+        assert!(select_market_index(
+            &zeta_group,
+            &greeks,
+            NOW + 30 * DAY,
+            7 * DAY,
+            delta(50),
+            Kind::Call
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_futures() {
+        let (zeta_group, greeks) = setup();
+        assert!(
+            select_market_index(&zeta_group, &greeks, NOW, DAY, delta(50), Kind::Future).is_err()
+        );
+    }
+}
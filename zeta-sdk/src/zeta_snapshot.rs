@@ -0,0 +1,245 @@
+// Borsh encoded snapshot of a Zeta expiry series and a margin account,
+// small enough to fit in a program's return data (1024 bytes).
+use crate::*;
+use std::convert::TryInto;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct ProductSnapshot {
+    pub kind: Kind,
+    pub strike: u64,     // 6.dp, 0 if unset
+    pub mark_price: u64, // 6.dp
+    pub delta: i64,      // PRICING_PRECISION, signed
+    pub volatility: u64, // PRICING_PRECISION, 0 for futures
+    pub position: i64,   // POSITION_PRECISION
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Debug)]
+pub struct MarketSnapshot {
+    pub expiry_index: u8,
+    pub expiry_ts: u64,
+    pub status: ExpirySeriesStatus,
+    pub oracle_price: u64, // 6.dp
+    pub balance: u64,
+    pub initial_margin: u64,
+    pub maintenance_margin: u64,
+    pub unrealized_pnl: i64,
+    // Products of the series in market index order, i.e. calls, puts then the future.
+    pub products: Vec<ProductSnapshot>,
+}
+
+impl MarketSnapshot {
+    /// Decodes the return data of `read_market_snapshot`.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        Self::try_from_slice(data).map_err(|_| ErrorCode::InvalidSnapshot.into())
+    }
+}
+
+/// Builds a snapshot of the series at `expiry_index` and the margin account.
+pub fn get_market_snapshot(
+    zeta_group: &ZetaGroup,
+    greeks: &Greeks,
+    margin_account: &MarginAccount,
+    oracle_price: u64,
+    expiry_index: usize,
+    current_ts: u64,
+) -> Result<MarketSnapshot> {
+    let expiry_series = zeta_group.expiry_series[expiry_index];
+    let positions = margin_account.get_positions_slice(expiry_index);
+    let mark_prices = greeks.get_mark_prices_slice(expiry_index);
+    let product_greeks = greeks.get_product_greeks_slice(expiry_index);
+
+    let mut products = Vec::with_capacity(NUM_PRODUCTS_PER_SERIES);
+    for (i, product) in zeta_group
+        .get_products_slice(expiry_index)
+        .iter()
+        .enumerate()
+    {
+        let market_index = get_products_slice_market_index(expiry_index, i);
+        let (delta, volatility) = match product.kind {
+            Kind::Call | Kind::Put => (
+                greeks.get_delta(market_index, product.kind)?,
+                (Decimal::from(product_greeks[i % NUM_STRIKES].volatility)
+                    * Decimal::from(10u64.pow(PRICING_PRECISION)))
+                .to_u64()
+                .ok_or(ErrorCode::MathOverflow)?,
+            ),
+            Kind::Future => (greeks.get_delta(market_index, product.kind)?, 0),
+            Kind::Uninitialized => (0, 0),
+        };
+        products.push(ProductSnapshot {
+            kind: product.kind,
+            strike: product.strike.get_strike().unwrap_or(0),
+            mark_price: mark_prices[i],
+            delta: delta.try_into().map_err(|_| ErrorCode::MathOverflow)?,
+            volatility,
+            position: positions[i].position,
+        });
+    }
+
+    Ok(MarketSnapshot {
+        expiry_index: expiry_index as u8,
+        expiry_ts: expiry_series.expiry_ts,
+        status: expiry_series.get_status(current_ts),
+        oracle_price,
+        balance: margin_account.balance,
+        initial_margin: margin_account.get_initial_margin(greeks, zeta_group, oracle_price)?,
+        maintenance_margin: margin_account.get_maintenance_margin(
+            greeks,
+            zeta_group,
+            oracle_price,
+        )?,
+        unrealized_pnl: margin_account.get_unrealized_pnl(greeks)?,
+        products,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    const MAX_RETURN_DATA: usize = 1024;
+    const NOW: u64 = 1_640_000_000;
+    const SPOT: u64 = 100_000_000; // $100
+    const LOT: u64 = 1_000; // POSITION_PRECISION
+    const PUT_INDEX: usize = NUM_STRIKES;
+    const CALL_MARK: u64 = 5_000_000;
+    const PUT_MARK: u64 = 4_000_000;
+    const FUTURE_MARK: u64 = SPOT + 1_000_000;
+
+    // Live front expiry with an ATM call and put, call delta 0.6 and vol 0.5.
+    // Long 2 calls bought at the put mark, short 1 future at spot with
+    // another lot offered. Only the futures margin parameters are set.
+    fn setup() -> (Box<ZetaGroup>, Box<Greeks>, Box<MarginAccount>) {
+        let mut zeta_group = Box::new(ZetaGroup::zeroed());
+        let mut greeks = Box::new(Greeks::zeroed());
+        let mut margin_account = Box::new(MarginAccount::zeroed());
+
+        zeta_group.expiry_series[0] = ExpirySeries {
+            active_ts: NOW - 100,
+            expiry_ts: NOW + 100,
+            dirty: false,
+            padding: [0; 15],
+        };
+        let strike = Strike {
+            is_set: true,
+            value: SPOT,
+        };
+        for (market_index, kind, mark_price) in [
+            (0, Kind::Call, CALL_MARK),
+            (PUT_INDEX, Kind::Put, PUT_MARK),
+            (SERIES_FUTURE_INDEX, Kind::Future, FUTURE_MARK),
+        ] {
+            zeta_group.products[market_index] = Product {
+                market: Pubkey::default(),
+                strike,
+                dirty: false,
+                kind,
+            };
+            greeks.mark_prices[market_index] = mark_price;
+        }
+        zeta_group.margin_parameters.future_margin_initial = 10_000_000;
+        zeta_group.margin_parameters.future_margin_maintenance = 5_000_000;
+        greeks.product_greeks[0] = ProductGreeks {
+            delta: 600_000_000_000,
+            vega: Decimal::new(1, 1).into(),
+            volatility: Decimal::new(5, 1).into(),
+        };
+
+        margin_account.balance = 50_000_000;
+        margin_account.positions[0] = Position {
+            position: 2 * LOT as i64,
+            cost_of_trades: 2 * PUT_MARK,
+            closing_orders: 0,
+            opening_orders: [0, 0],
+        };
+        margin_account.positions[SERIES_FUTURE_INDEX] = Position {
+            position: -(LOT as i64),
+            cost_of_trades: SPOT,
+            closing_orders: 0,
+            opening_orders: [0, LOT],
+        };
+        (zeta_group, greeks, margin_account)
+    }
+
+    #[test]
+    fn snapshot_matches_fixture() {
+        let (zeta_group, greeks, margin_account) = setup();
+        let snapshot =
+            get_market_snapshot(&zeta_group, &greeks, &margin_account, SPOT, 0, NOW).unwrap();
+
+        assert_eq!(snapshot.expiry_index, 0);
+        assert_eq!(snapshot.expiry_ts, NOW + 100);
+        assert_eq!(snapshot.status, ExpirySeriesStatus::Live);
+        assert_eq!(snapshot.oracle_price, SPOT);
+        assert_eq!(snapshot.balance, 50_000_000);
+        // 10% of spot on the offered future lot.
+        assert_eq!(snapshot.initial_margin, SPOT / 10);
+        // 5% of spot on the short future lot.
+        assert_eq!(snapshot.maintenance_margin, SPOT / 20);
+        // Calls +$2, future -$1.
+        assert_eq!(snapshot.unrealized_pnl, 1_000_000);
+
+        assert_eq!(snapshot.products.len(), NUM_PRODUCTS_PER_SERIES);
+        assert_eq!(
+            snapshot.products[0],
+            ProductSnapshot {
+                kind: Kind::Call,
+                strike: SPOT,
+                mark_price: CALL_MARK,
+                delta: 600_000_000_000,
+                volatility: 500_000_000_000,
+                position: 2 * LOT as i64,
+            }
+        );
+        assert_eq!(
+            snapshot.products[PUT_INDEX],
+            ProductSnapshot {
+                kind: Kind::Put,
+                strike: SPOT,
+                mark_price: PUT_MARK,
+                delta: -400_000_000_000,
+                volatility: 500_000_000_000,
+                position: 0,
+            }
+        );
+        assert_eq!(
+            snapshot.products[SERIES_FUTURE_INDEX],
+            ProductSnapshot {
+                kind: Kind::Future,
+                strike: SPOT,
+                mark_price: FUTURE_MARK,
+                delta: 1_000_000_000_000,
+                volatility: 0,
+                position: -(LOT as i64),
+            }
+        );
+        assert_eq!(snapshot.products[1].kind, Kind::Uninitialized);
+    }
+
+    #[test]
+    fn fits_in_return_data_and_round_trips() {
+        let product = ProductSnapshot {
+            kind: Kind::Call,
+            strike: u64::MAX,
+            mark_price: u64::MAX,
+            delta: i64::MIN,
+            volatility: u64::MAX,
+            position: i64::MIN,
+        };
+        let snapshot = MarketSnapshot {
+            expiry_index: 1,
+            expiry_ts: u64::MAX,
+            status: ExpirySeriesStatus::Live,
+            oracle_price: u64::MAX,
+            balance: u64::MAX,
+            initial_margin: u64::MAX,
+            maintenance_margin: u64::MAX,
+            unrealized_pnl: i64::MIN,
+            products: vec![product; NUM_PRODUCTS_PER_SERIES],
+        };
+        let data = snapshot.try_to_vec().unwrap();
+        assert!(data.len() <= MAX_RETURN_DATA);
+        assert_eq!(MarketSnapshot::decode(&data).unwrap(), snapshot);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::*;
+use anchor_lang::Discriminator;
+use std::cell::{Ref, RefMut};
+use std::convert::{TryFrom, TryInto};
+use std::mem::size_of;
+use std::ops::DerefMut;
+
+#[macro_export]
+macro_rules! wrap_error {
+    ($err:expr) => {{
+        msg!("Error thrown at {}:{}", file!(), line!());
+        $err
+    }};
+}
+
+// Skips the discriminator without any validation, prefer the `load_*` helpers below.
+pub fn deserialize_account_info_zerocopy<'a, T: bytemuck::Pod>(
+    account_info: &'a AccountInfo,
+) -> Result<RefMut<'a, T>> {
+    let data = account_info.try_borrow_mut_data()?;
+    Ok(RefMut::map(data, |data| {
+        bytemuck::from_bytes_mut(&mut data.deref_mut()[8..])
+    }))
+}
+
+/// Read-only zero copy load of a Zeta account.
+/// Verifies the account is owned by the Zeta program, is large enough for `T`
+/// and starts with the Anchor discriminator of `T`.
+pub fn load_zeta_account<'a, T: bytemuck::Pod + Discriminator>(
+    account_info: &'a AccountInfo,
+) -> Result<Ref<'a, T>> {
+    validate_zeta_account::<T>(account_info)?;
+    let data = account_info.try_borrow_data()?;
+    Ok(Ref::map(data, |data| {
+        bytemuck::from_bytes(&data[DISCRIMINATOR_SIZE..DISCRIMINATOR_SIZE + size_of::<T>()])
+    }))
+}
+
+/// Mutable variant of `load_zeta_account`, also requires the account to be writable.
+pub fn load_zeta_account_mut<'a, T: bytemuck::Pod + Discriminator>(
+    account_info: &'a AccountInfo,
+) -> Result<RefMut<'a, T>> {
+    if !account_info.is_writable {
+        return wrap_error!(Err(ErrorCode::AccountNotMutable.into()));
+    }
+    validate_zeta_account::<T>(account_info)?;
+    let data = account_info.try_borrow_mut_data()?;
+    Ok(RefMut::map(data, |data| {
+        bytemuck::from_bytes_mut(
+            &mut data.deref_mut()[DISCRIMINATOR_SIZE..DISCRIMINATOR_SIZE + size_of::<T>()],
+        )
+    }))
+}
+
+fn validate_zeta_account<T: Discriminator>(account_info: &AccountInfo) -> Result<()> {
+    if account_info.owner != &crate::ID {
+        msg!("Account owner = {}", account_info.owner);
+        return wrap_error!(Err(ErrorCode::InvalidAccountOwner.into()));
+    }
+    let data = account_info.try_borrow_data()?;
+    if data.len() < DISCRIMINATOR_SIZE + size_of::<T>() {
+        msg!("Account length = {}", data.len());
+        return wrap_error!(Err(ErrorCode::InvalidAccountLength.into()));
+    }
+    if data[..DISCRIMINATOR_SIZE] != T::discriminator() {
+        return wrap_error!(Err(ErrorCode::InvalidAccountDiscriminator.into()));
+    }
+    Ok(())
+}
+
+pub fn load_zeta_group<'a>(account_info: &'a AccountInfo) -> Result<Ref<'a, ZetaGroup>> {
+    load_zeta_account(account_info)
+}
+
+pub fn load_zeta_group_mut<'a>(account_info: &'a AccountInfo) -> Result<RefMut<'a, ZetaGroup>> {
+    load_zeta_account_mut(account_info)
+}
+
+pub fn load_greeks<'a>(account_info: &'a AccountInfo) -> Result<Ref<'a, Greeks>> {
+    load_zeta_account(account_info)
+}
+
+pub fn load_greeks_mut<'a>(account_info: &'a AccountInfo) -> Result<RefMut<'a, Greeks>> {
+    load_zeta_account_mut(account_info)
+}
+
+pub fn load_margin_account<'a>(account_info: &'a AccountInfo) -> Result<Ref<'a, MarginAccount>> {
+    load_zeta_account(account_info)
+}
+
+pub fn load_margin_account_mut<'a>(
+    account_info: &'a AccountInfo,
+) -> Result<RefMut<'a, MarginAccount>> {
+    load_zeta_account_mut(account_info)
+}
+
+#[inline(never)]
+pub fn deserialize_account_info<'a, T: AccountSerialize + AccountDeserialize + Owner + Clone>(
+    account_info: &AccountInfo<'a>,
+) -> Result<T> {
+    let mut data: &[u8] = &account_info.try_borrow_data()?;
+    Ok(T::try_deserialize_unchecked(&mut data)?)
+}
+
+pub fn get_otm_amount(spot: u64, strike: u64, product: Kind) -> Result<u64> {
+    let otm_amount = match product {
+        Kind::Call => (strike as i128).checked_sub(spot as i128),
+        Kind::Put => (spot as i128).checked_sub(strike as i128),
+        // Futures have no strike so are never out of the money.
+        Kind::Future => return Ok(0),
+        _ => return wrap_error!(Err(ErrorCode::UnsupportedKind.into())),
+    };
+    otm_amount
+        .ok_or(ErrorCode::MathOverflow)?
+        .max(0)
+        .try_into()
+        .map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Initial margin for single product
+pub fn get_initial_margin_per_lot(
+    spot: u64,
+    strike: u64,
+    mark: u64,
+    product: Kind,
+    side: Side,
+    margin_parameters: &MarginParameters,
+) -> Result<u64> {
+    if spot == 0 {
+        return wrap_error!(Err(ErrorCode::ZeroSpot.into()));
+    }
+    let initial_margin: u128 = match product {
+        Kind::Future => (spot as u128)
+            .checked_mul(margin_parameters.future_margin_initial.into())
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(NATIVE_PRECISION_DENOMINATOR)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Kind::Call | Kind::Put => match side {
+            Side::Bid => (spot as u128)
+                .checked_mul(margin_parameters.option_spot_percentage_long_initial.into())
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(NATIVE_PRECISION_DENOMINATOR)
+                .ok_or(ErrorCode::MathOverflow)?
+                .min(
+                    (mark as u128)
+                        .checked_mul(margin_parameters.option_mark_percentage_long_initial.into())
+                        .ok_or(ErrorCode::MathOverflow)?
+                        .checked_div(NATIVE_PRECISION_DENOMINATOR)
+                        .ok_or(ErrorCode::MathOverflow)?,
+                ),
+            Side::Ask => {
+                let otm_amount: u128 = get_otm_amount(spot, strike, product)?.into();
+                let otm_pct = otm_amount
+                    .checked_mul(NATIVE_PRECISION_DENOMINATOR)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(spot.into())
+                    .ok_or(ErrorCode::MathOverflow)?;
+
+                let dynamic_margin_pct = (margin_parameters.option_dynamic_percentage_short_initial
+                    as u128)
+                    .checked_sub(otm_pct)
+                    .unwrap_or(0);
+
+                let margin_pct = dynamic_margin_pct.max(
+                    margin_parameters
+                        .option_spot_percentage_short_initial
+                        .into(),
+                );
+                margin_pct
+                    .checked_mul(spot.into())
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(NATIVE_PRECISION_DENOMINATOR)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+            Side::Uninitialized => return wrap_error!(Err(ErrorCode::InvalidSide.into())),
+        },
+        _ => return wrap_error!(Err(ErrorCode::UnsupportedKind.into())),
+    };
+
+    if product == Kind::Put && side == Side::Ask {
+        let sell_put_cap_margin = (strike as u128)
+            .checked_mul(margin_parameters.option_short_put_cap_percentage as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(NATIVE_PRECISION_DENOMINATOR)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        return u64::try_from(initial_margin.min(sell_put_cap_margin))
+            .map_err(|_| ErrorCode::MathOverflow.into());
+    }
+
+    u64::try_from(initial_margin).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Maintenance margin for single product
+pub fn get_maintenance_margin_per_lot(
+    spot: u64,
+    strike: u64,
+    mark: u64,
+    product: Kind,
+    long: bool,
+    margin_parameters: &MarginParameters,
+) -> Result<u64> {
+    if spot == 0 {
+        return wrap_error!(Err(ErrorCode::ZeroSpot.into()));
+    }
+    let maintenance_margin: u128 = match product {
+        Kind::Future => (spot as u128)
+            .checked_mul(margin_parameters.future_margin_maintenance.into())
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(NATIVE_PRECISION_DENOMINATOR)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Kind::Call | Kind::Put => {
+            if long {
+                (spot as u128)
+                    .checked_mul(
+                        margin_parameters
+                            .option_spot_percentage_long_maintenance
+                            .into(),
+                    )
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(NATIVE_PRECISION_DENOMINATOR)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .min(
+                        (mark as u128)
+                            .checked_mul(
+                                margin_parameters
+                                    .option_mark_percentage_long_maintenance
+                                    .into(),
+                            )
+                            .ok_or(ErrorCode::MathOverflow)?
+                            .checked_div(NATIVE_PRECISION_DENOMINATOR)
+                            .ok_or(ErrorCode::MathOverflow)?,
+                    )
+            } else {
+                let otm_amount: u128 = get_otm_amount(spot, strike, product)?.into();
+                let otm_pct = otm_amount
+                    .checked_mul(NATIVE_PRECISION_DENOMINATOR)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(spot.into())
+                    .ok_or(ErrorCode::MathOverflow)?;
+
+                let dynamic_margin_pct: u128 =
+                    (margin_parameters.option_dynamic_percentage_short_maintenance as u128)
+                        .checked_sub(otm_pct)
+                        .unwrap_or(0);
+
+                let margin_pct = dynamic_margin_pct.max(
+                    margin_parameters
+                        .option_spot_percentage_short_maintenance
+                        .into(),
+                );
+                margin_pct
+                    .checked_mul(spot.into())
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(NATIVE_PRECISION_DENOMINATOR)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+        }
+        _ => return wrap_error!(Err(ErrorCode::UnsupportedKind.into())),
+    };
+
+    if product == Kind::Put && !long {
+        let sell_put_cap_margin = (strike as u128)
+            .checked_mul(margin_parameters.option_short_put_cap_percentage as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(NATIVE_PRECISION_DENOMINATOR)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        return u64::try_from(maintenance_margin.min(sell_put_cap_margin))
+            .map_err(|_| ErrorCode::MathOverflow.into());
+    }
+
+    u64::try_from(maintenance_margin).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Returns the native oracle price (6.dp) after validating the account is a
+/// trading Pyth price feed that is recent and has a tight enough confidence.
+///
+/// # Arguments
+///
+/// * `oracle` - Oracle account.
+/// * `config` - Max slot age and confidence bounds.
+pub fn get_validated_native_oracle_price(
+    oracle: &AccountInfo,
+    config: &pyth_client::PriceValidationConfig,
+) -> Result<u64> {
+    let oracle_price = pyth_client::Price::load_checked(&oracle)?;
+    let clock = Clock::get()?;
+    let agg = oracle_price.get_validated_price(clock.slot, config)?;
+    oracle_price.scale_to_precision(agg.price as u64, PLATFORM_PRECISION)
+}
+
+/// Returns the market index given an expiry index and index into the slice.
+///
+/// # Arguments
+///
+/// * `expiry_index` - Expiry series index.
+/// * `product_index` - Index into the products slice. [0..NUM_PRODUCTS_PER_SERIES).
+pub fn get_products_slice_market_index(expiry_index: usize, product_index: usize) -> usize {
+    expiry_index
+        .checked_mul(NUM_PRODUCTS_PER_SERIES)
+        .unwrap()
+        .checked_add(product_index)
+        .unwrap()
+}
+
+// Index into `Position::opening_orders`, bids first.
+pub fn get_side_index(side: Side) -> Result<usize> {
+    match side {
+        Side::Bid => Ok(0),
+        Side::Ask => Ok(1),
+        Side::Uninitialized => wrap_error!(Err(ErrorCode::InvalidSide.into())),
+    }
+}
+
+/// Rejects orders Zeta would fail on before making the CPI. Serum takes
+/// price and size in lots, so they must be whole multiples of the market's
+/// tick and lot sizes.
+pub fn validate_order(
+    market: &serum_market::MarketParameters,
+    price: u64,
+    size: u64,
+    side: Side,
+) -> Result<()> {
+    if side == Side::Uninitialized {
+        return wrap_error!(Err(ErrorCode::InvalidSide.into()));
+    }
+    if price == 0 || price % market.pc_lot_size != 0 {
+        msg!("Price = {}, tick size = {}", price, market.pc_lot_size);
+        return wrap_error!(Err(ErrorCode::InvalidOrderPrice.into()));
+    }
+    if size == 0 || size % market.coin_lot_size != 0 {
+        msg!("Size = {}, lot size = {}", size, market.coin_lot_size);
+        return wrap_error!(Err(ErrorCode::InvalidOrderSize.into()));
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+// Implied volatility surface built from the f/k nodes and per expiry
+// volatility points published in the greeks account.
+use crate::*;
+
+/// Interpolates the implied volatility of `strike` on the curve of
+/// `expiry_index`. Linear in f/k between nodes and flat beyond the outer nodes.
+///
+/// # Arguments
+///
+/// * `forward` - Underlying forward (6.dp), e.g. `Greeks::get_futures_price`.
+/// * `strike` - Strike (6.dp), need not be a listed product.
+///
+/// Returns the volatility as a fraction, same convention as `ProductGreeks.volatility`.
+pub fn get_volatility(
+    greeks: &Greeks,
+    expiry_index: usize,
+    forward: u64,
+    strike: u64,
+) -> Result<Decimal> {
+    if forward == 0 || strike == 0 {
+        return wrap_error!(Err(ErrorCode::InvalidPricingInputs.into()));
+    }
+    let nodes = greeks.nodes;
+    let volatility = greeks.get_volatility_slice(expiry_index);
+    let moneyness = Decimal::from(forward) / Decimal::from(strike);
+
+    // Sort the curve by f/k so nodes may be published in either direction.
+    let mut points = [(Decimal::zero(), Decimal::zero()); VOLATILITY_POINTS];
+    for i in 0..VOLATILITY_POINTS {
+        points[i] = (
+            Decimal::new(nodes[i] as i64, PRICING_PRECISION),
+            Decimal::new(volatility[i] as i64, PRICING_PRECISION),
+        );
+    }
+    points.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let (first, last) = (points[0], points[VOLATILITY_POINTS - 1]);
+    if moneyness <= first.0 {
+        return Ok(first.1);
+    }
+    if moneyness >= last.0 {
+        return Ok(last.1);
+    }
+    for window in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+        if moneyness <= x1 {
+            if x1 == x0 {
+                return Ok(y1);
+            }
+            return Ok(y0 + (y1 - y0) * (moneyness - x0) / (x1 - x0));
+        }
+    }
+    Ok(last.1)
+}
+
+/// Interpolates the implied volatility of `strike` at an arbitrary expiry,
+/// linearly in total variance (vol^2 * t) between the listed expiries.
+/// Expiries before the front or after the back expiry take its volatility.
+///
+/// # Arguments
+///
+/// * `forward` - Underlying forward (6.dp).
+/// * `strike` - Strike (6.dp).
+/// * `expiry_ts` - Target expiry, must be after `current_ts`.
+pub fn get_volatility_at_expiry(
+    zeta_group: &ZetaGroup,
+    greeks: &Greeks,
+    forward: u64,
+    strike: u64,
+    expiry_ts: u64,
+    current_ts: u64,
+) -> Result<Decimal> {
+    if expiry_ts <= current_ts {
+        return wrap_error!(Err(ErrorCode::InvalidPricingInputs.into()));
+    }
+
+    // Unexpired series ordered by expiry.
+    let mut expiries = [(0u64, 0usize); ACTIVE_EXPIRIES];
+    let mut len = 0;
+    for (expiry_index, series) in zeta_group.expiry_series.iter().enumerate() {
+        if series.get_status(current_ts) == ExpirySeriesStatus::Live {
+            expiries[len] = (series.expiry_ts, expiry_index);
+            len += 1;
+        }
+    }
+    if len == 0 {
+        return wrap_error!(Err(ErrorCode::MarketNotLive.into()));
+    }
+    let expiries = &mut expiries[..len];
+    expiries.sort_unstable();
+
+    let (front_ts, front_index) = expiries[0];
+    if expiry_ts <= front_ts {
+        return get_volatility(greeks, front_index, forward, strike);
+    }
+    let (back_ts, back_index) = expiries[len - 1];
+    if expiry_ts >= back_ts {
+        return get_volatility(greeks, back_index, forward, strike);
+    }
+
+    for window in expiries.windows(2) {
+        let ((ts0, index0), (ts1, index1)) = (window[0], window[1]);
+        if expiry_ts <= ts1 {
+            let t0 = Decimal::from(ts0 - current_ts);
+            let t1 = Decimal::from(ts1 - current_ts);
+            let t = Decimal::from(expiry_ts - current_ts);
+            let vol0 = get_volatility(greeks, index0, forward, strike)?;
+            let vol1 = get_volatility(greeks, index1, forward, strike)?;
+            let variance0 = vol0 * vol0 * t0;
+            let variance1 = vol1 * vol1 * t1;
+            let variance = variance0 + (variance1 - variance0) * (t - t0) / (t1 - t0);
+            return (variance / t)
+                .sqrt()
+                .ok_or(ErrorCode::InvalidPricingInputs.into());
+        }
+    }
+    get_volatility(greeks, back_index, forward, strike)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    const NOW: u64 = 1_640_000_000;
+    const DAY: u64 = 86_400;
+    const PRICE: u64 = 100_000_000;
+
+    fn native(value: u64, pct: u64) -> u64 {
+        value * 10u64.pow(PRICING_PRECISION) / 100 * pct / 100
+    }
+
+    // f/k nodes at 0.8, 0.9, 1.0, 1.1, 1.2. Expiry 0 (1 day) has a smile
+    // from 80% to 60% and back, expiry 1 (8 days) is flat at 50%.
+    fn setup() -> (Box<ZetaGroup>, Box<Greeks>) {
+        let mut zeta_group = Box::new(ZetaGroup::zeroed());
+        let mut greeks = Box::new(Greeks::zeroed());
+        greeks.nodes = [80, 90, 100, 110, 120].map(|node| native(100, node));
+        greeks.volatility = [80, 70, 60, 70, 80, 50, 50, 50, 50, 50].map(|vol| native(100, vol));
+
+        for (expiry_index, days) in [1, 8].iter().enumerate() {
+            zeta_group.expiry_series[expiry_index] = ExpirySeries {
+                active_ts: NOW - DAY,
+                expiry_ts: NOW + days * DAY,
+                dirty: false,
+                padding: [0; 15],
+            };
+        }
+        (zeta_group, greeks)
+    }
+
+    #[test]
+    fn interpolates_between_nodes() {
+        let (_, greeks) = setup();
+        // f/k = 0.95, halfway between 70% and 60%.
+        let vol = get_volatility(&greeks, 0, 95 * PRICE / 100, PRICE).unwrap();
+        assert_eq!(vol.round_dp(6), Decimal::new(65, 2));
+    }
+
+    #[test]
+    fn extrapolates_flat() {
+        let (_, greeks) = setup();
+        let vol = get_volatility(&greeks, 0, PRICE, 2 * PRICE).unwrap();
+        assert_eq!(vol.round_dp(6), Decimal::new(80, 2));
+    }
+
+    #[test]
+    fn interpolates_total_variance_across_expiries() {
+        let (zeta_group, greeks) = setup();
+        // ATM: 60% at 1 day, 50% at 8 days.
+        let vol = get_volatility_at_expiry(&zeta_group, &greeks, PRICE, PRICE, NOW + 4 * DAY, NOW)
+            .unwrap();
+        // variance(4d) = 0.36 + (2.0 - 0.36) * 3 / 7
+        let expected = ((Decimal::new(36, 2)
+            + (Decimal::new(200, 2) - Decimal::new(36, 2)) * Decimal::from(3) / Decimal::from(7))
+            / Decimal::from(4))
+        .sqrt()
+        .unwrap();
+        assert_eq!(vol.round_dp(6), expected.round_dp(6));
+    }
+}